@@ -4,9 +4,11 @@ extern crate chaskey;
 extern crate rand;
 extern crate test;
 
-use chaskey::{Digester, Chaskey};
+use chaskey::{ChaskeyBuildHasher, Digester, Chaskey, MultiDigester};
 use rand::{Rng, ThreadRng, thread_rng};
-use std::hash::{SipHasher, Hasher};
+use std::collections::HashMap;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hash, SipHasher, Hasher};
 use test::{black_box, Bencher};
 
 
@@ -28,6 +30,32 @@ fn chaskey_hasher(b: &mut Bencher) {
     bench_hasher(b, &mut hasher, SIZE);
 }
 
+#[bench]
+fn chaskey_hasher_u64_bytes(b: &mut Bencher) {
+    let mut rng: ThreadRng = thread_rng();
+    let key: [u32; 4] = rng.gen();
+    let n: u64 = rng.gen();
+    let builder: ChaskeyBuildHasher<Chaskey> = ChaskeyBuildHasher::with_key(key);
+    b.iter(|| {
+        let mut hasher = builder.build_hasher();
+        Hasher::write(&mut hasher, &black_box(n).to_ne_bytes());
+        black_box(Hasher::finish(&hasher));
+    });
+}
+
+#[bench]
+fn chaskey_hasher_u64_words(b: &mut Bencher) {
+    let mut rng: ThreadRng = thread_rng();
+    let key: [u32; 4] = rng.gen();
+    let n: u64 = rng.gen();
+    let builder: ChaskeyBuildHasher<Chaskey> = ChaskeyBuildHasher::with_key(key);
+    b.iter(|| {
+        let mut hasher = builder.build_hasher();
+        hasher.write_u64(black_box(n));
+        black_box(Hasher::finish(&hasher));
+    });
+}
+
 fn bench_hasher<H: Hasher>(b: &mut Bencher, hasher: &mut H, size: usize) {
     let data: Vec<u8> = {
         let mut r = vec![0; size];
@@ -42,3 +70,90 @@ fn bench_hasher<H: Hasher>(b: &mut Bencher, hasher: &mut H, size: usize) {
     });
 }
 
+
+#[bench]
+fn chaskey_sequential_4(b: &mut Bencher) {
+    let mut rng: ThreadRng = thread_rng();
+    let keys: [[u32; 4]; 4] = [rng.gen(), rng.gen(), rng.gen(), rng.gen()];
+    let data = random_messages(4, SIZE);
+    b.iter(|| {
+        for (key, msg) in keys.iter().zip(data.iter()) {
+            let mut mac: Digester<Chaskey> = Digester::new(*key);
+            mac.write(msg);
+            black_box(mac.finish());
+        }
+    });
+}
+
+#[bench]
+fn chaskey_multi_4(b: &mut Bencher) {
+    let mut rng: ThreadRng = thread_rng();
+    let keys: [[u32; 4]; 4] = [rng.gen(), rng.gen(), rng.gen(), rng.gen()];
+    let data = random_messages(4, SIZE);
+    let mac: MultiDigester<Chaskey, 4> = MultiDigester::new(keys);
+    b.iter(|| {
+        black_box(mac.digest([&data[0], &data[1], &data[2], &data[3]]));
+    });
+}
+
+fn random_messages(n: usize, size: usize) -> Vec<Vec<u8>> {
+    let mut rng: ThreadRng = thread_rng();
+    (0..n).map(|_| {
+        let mut r = vec![0; size];
+        rng.fill_bytes(&mut r);
+        r
+    }).collect()
+}
+
+#[bench]
+fn map_u32_sip(b: &mut Bencher) {
+    bench_map(b, RandomState::new(), |rng| rng.gen::<u32>());
+}
+
+#[bench]
+fn map_u32_chaskey(b: &mut Bencher) {
+    bench_map(b, ChaskeyBuildHasher::<Chaskey>::new(), |rng| rng.gen::<u32>());
+}
+
+#[bench]
+fn map_u64_sip(b: &mut Bencher) {
+    bench_map(b, RandomState::new(), |rng| rng.gen::<u64>());
+}
+
+#[bench]
+fn map_u64_chaskey(b: &mut Bencher) {
+    bench_map(b, ChaskeyBuildHasher::<Chaskey>::new(), |rng| rng.gen::<u64>());
+}
+
+#[bench]
+fn map_string_sip(b: &mut Bencher) {
+    bench_map(b, RandomState::new(), random_string);
+}
+
+#[bench]
+fn map_string_chaskey(b: &mut Bencher) {
+    bench_map(b, ChaskeyBuildHasher::<Chaskey>::new(), random_string);
+}
+
+fn random_string(rng: &mut ThreadRng) -> String {
+    rng.gen_ascii_chars().take(12).collect()
+}
+
+/// Look up every key of a 1,000-entry map built with the given
+/// `BuildHasher`.
+fn bench_map<K, B, F>(b: &mut Bencher, builder: B, mut gen_key: F)
+    where K: Hash + Eq, B: BuildHasher, F: FnMut(&mut ThreadRng) -> K
+{
+    const ENTRIES: usize = 1000;
+    let mut rng: ThreadRng = thread_rng();
+    let mut map: HashMap<K, usize, B> = HashMap::with_hasher(builder);
+    for i in 0..ENTRIES {
+        map.insert(gen_key(&mut rng), i);
+    }
+
+    b.iter(|| {
+        for key in map.keys() {
+            black_box(map.get(key));
+        }
+    });
+}
@@ -0,0 +1,112 @@
+//! Generate the known-answer test files in `tests/kat`.
+//!
+//! ```text
+//! cargo run --example katgen [DIRECTORY]
+//! ```
+//!
+//! The vectors are computed with this crate, so regenerating the
+//! files only makes sense after the implementation has been checked
+//! against the published test vectors.  The inputs are fixed, so the
+//! output is always the same.
+
+extern crate chaskey;
+
+use chaskey::Variant;
+use chaskey::kat::{self, Vector};
+use std::env;
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+
+
+/// The key of the published Chaskey test vectors.
+const KEY: [u32; 4] = [0x833D3433, 0x009F389F, 0x2398E64F, 0x417ACF39];
+
+/// The key of the FELICS Chaskey-LTS block cipher test vector.
+const FELICS_KEY: [u32; 4] = [0x68e90956, 0x29e3585f, 0x98ecec40, 0x2f9822c5];
+const FELICS_PLAINTEXT: [u32; 4] = [0x262823b8, 0x5e405efd, 0xa901a369, 0xd87aea78];
+
+/// Message lengths for the MAC vectors with pseudo-random inputs,
+/// chosen around the block boundaries.
+const LENGTHS: [usize; 16] = [64, 65, 79, 80, 81, 95, 96, 97,
+                              127, 128, 129, 255, 256, 257, 1000, 1024];
+
+
+/// A xorshift64* generator, so that the "random" inputs are
+/// reproducible.
+struct Xorshift(u64);
+
+impl Xorshift {
+    fn next_u32(&mut self) -> u32 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        (self.0.wrapping_mul(0x2545F4914F6CDD1D) >> 32) as u32
+    }
+
+    fn block(&mut self) -> [u32; 4] {
+        [self.next_u32(), self.next_u32(), self.next_u32(), self.next_u32()]
+    }
+
+    fn bytes(&mut self, len: usize) -> Vec<u8> {
+        (0..len).map(|_| self.next_u32() as u8).collect()
+    }
+}
+
+fn vectors(variant: Variant) -> Vec<Vector> {
+    let mut rng = Xorshift(0x636861736b6579);
+    let mut vectors = Vec::new();
+
+    // The same inputs as the published test vectors.
+    for len in 0..64 {
+        vectors.push(Vector::mac(variant, KEY, (0..len as u8).collect()));
+    }
+    for len in LENGTHS.iter() {
+        let key = rng.block();
+        vectors.push(Vector::mac(variant, key, rng.bytes(*len)));
+    }
+
+    if variant == Variant::ChaskeyLTS {
+        vectors.push(Vector::cipher(variant, FELICS_KEY, FELICS_PLAINTEXT));
+    }
+    vectors.push(Vector::cipher(variant, KEY, [0, 0, 0, 0]));
+    for _ in 0..16 {
+        let key = rng.block();
+        vectors.push(Vector::cipher(variant, key, rng.block()));
+    }
+    vectors
+}
+
+fn header(variant: Variant) -> String {
+    let mut header = format!("\
+# {} known-answer tests, in the format described in the
+# documentation of the `chaskey::kat` module.
+#
+# Generated by `cargo run --example katgen`; do not edit.
+#
+# MAC vectors 0 to 63 have the key and messages of the published
+# Chaskey test vectors: the message of vector i is the bytes 0, 1,
+# ..., i - 1.
+", variant);
+    if variant == Variant::ChaskeyLTS {
+        header.push_str("#\n# Cipher vector 0 is the FELICS Chaskey-LTS test vector.\n");
+    }
+    header.push('\n');
+    header
+}
+
+fn main() {
+    let dir = env::args().nth(1).map(PathBuf::from).unwrap_or_else(|| {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("kat")
+    });
+    for &variant in [Variant::Chaskey, Variant::Chaskey12, Variant::ChaskeyLTS].iter() {
+        let path = dir.join(format!("{}.kat", variant));
+        let mut file = File::create(&path).unwrap_or_else(|e| {
+            panic!("can't create {}: {}", path.display(), e)
+        });
+        file.write_all(header(variant).as_bytes())
+            .and_then(|_| file.write_all(kat::format(&vectors(variant)).as_bytes()))
+            .unwrap_or_else(|e| panic!("can't write {}: {}", path.display(), e));
+        println!("wrote {}", path.display());
+    }
+}
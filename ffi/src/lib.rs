@@ -0,0 +1,15 @@
+//! The `chaskey` crate's C interface, declared in
+//! `include/chaskey.h`, built as C libraries:
+//!
+//! ```text
+//! cargo build --release -p chaskey-ffi
+//! ```
+//!
+//! produces `libchaskey_ffi.a` and `libchaskey_ffi.so` (or the
+//! platform's equivalents) in `target/release`.  The `chaskey` crate
+//! itself only builds as a Rust library, so that its dependents don't
+//! also build C libraries they don't need.
+
+extern crate chaskey;
+
+pub use chaskey::ffi::*;
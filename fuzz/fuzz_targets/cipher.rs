@@ -0,0 +1,29 @@
+//! `decrypt` inverts `encrypt`, and `ctr` is its own inverse.
+
+#![no_main]
+
+use chaskey::cipher::{ctr, decrypt, encrypt};
+use chaskey::core::Permutation;
+use chaskey::{Chaskey, Chaskey12, ChaskeyLTS};
+use libfuzzer_sys::fuzz_target;
+
+fn check<P: Permutation>(key: [u32; 4], block: [u32; 4], nonce: [u32; 4], data: &[u8]) {
+    let mut b = block;
+    encrypt::<P>(&mut b, &key);
+    decrypt::<P>(&mut b, &key);
+    assert_eq!(b, block);
+
+    let mut d = data.to_vec();
+    ctr::<P>(&key, &nonce, &mut d);
+    ctr::<P>(&key, &nonce, &mut d);
+    assert_eq!(d, data);
+}
+
+fuzz_target!(|input: (u8, [u32; 4], [u32; 4], [u32; 4], Vec<u8>)| {
+    let (variant, key, block, nonce, data) = input;
+    match variant % 3 {
+        0 => check::<Chaskey>(key, block, nonce, &data),
+        1 => check::<Chaskey12>(key, block, nonce, &data),
+        _ => check::<ChaskeyLTS>(key, block, nonce, &data),
+    }
+});
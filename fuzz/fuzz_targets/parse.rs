@@ -0,0 +1,58 @@
+//! The text parsers never panic, and what they accept survives a
+//! round trip through the matching formatter.
+
+#![no_main]
+
+use chaskey::clock::ManualClock;
+use chaskey::keyfile::{self, KeyFile};
+use chaskey::keyring::KeyedTag;
+use chaskey::token::Verifier;
+use chaskey::{kat, Chaskey, Tag, TruncatedTag, Variant};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let text = match std::str::from_utf8(data) {
+        Ok(text) => text,
+        Err(_) => return,
+    };
+
+    if let Ok(tag) = text.parse::<Tag>() {
+        assert_eq!(tag.to_string().parse::<Tag>(), Ok(tag));
+        assert_eq!(tag.to_string(), text.to_lowercase());
+    }
+
+    if let Ok(tag) = text.parse::<TruncatedTag>() {
+        assert_eq!(tag.to_string().parse::<TruncatedTag>(), Ok(tag));
+        assert_eq!(tag.to_string(), text.to_lowercase());
+    }
+
+    if let Ok(tag) = text.parse::<KeyedTag>() {
+        assert_eq!(tag.to_string().parse::<KeyedTag>(), Ok(tag));
+        assert_eq!(tag.to_string(), text.to_lowercase());
+    }
+
+    if let Ok(variant) = text.parse::<Variant>() {
+        assert_eq!(variant.name().parse::<Variant>(), Ok(variant));
+    }
+
+    if let Ok(key) = keyfile::parse_key(text) {
+        assert_eq!(keyfile::parse_key(&keyfile::format_key(&key)), Ok(key));
+    }
+
+    if let Ok(file) = text.parse::<KeyFile>() {
+        assert_eq!(file.to_string().parse::<KeyFile>(), Ok(file));
+    }
+
+    if let Ok(vectors) = kat::parse(text) {
+        for vector in &vectors {
+            vector.check();
+        }
+        assert_eq!(kat::parse(&kat::format(&vectors)), Ok(vectors));
+    }
+
+    // Nothing the fuzzer comes up with should verify, but rejecting
+    // it mustn't panic either.
+    let mut verifier: Verifier<Chaskey, _> = Verifier::with_clock(ManualClock::new(0));
+    verifier.add_key(1, [1, 2, 3, 4]);
+    assert!(verifier.verify(text).is_err());
+});
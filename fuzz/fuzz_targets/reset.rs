@@ -0,0 +1,30 @@
+//! A digester that has been `reset` behaves like a fresh one, whatever
+//! was written to it before.
+
+#![no_main]
+
+use chaskey::core::Permutation;
+use chaskey::{Chaskey, Chaskey12, ChaskeyLTS, Digester};
+use libfuzzer_sys::fuzz_target;
+
+fn check<P: Permutation>(key: [u32; 4], before: &[u8], message: &[u8]) {
+    let mut fresh: Digester<P> = Digester::new(key);
+    fresh.write(message);
+
+    let mut reused: Digester<P> = Digester::new(key);
+    reused.write(before);
+    reused.finish();
+    reused.reset();
+    reused.write(message);
+
+    assert_eq!(fresh.finish(), reused.finish());
+}
+
+fuzz_target!(|input: (u8, [u32; 4], Vec<u8>, Vec<u8>)| {
+    let (variant, key, before, message) = input;
+    match variant % 3 {
+        0 => check::<Chaskey>(key, &before, &message),
+        1 => check::<Chaskey12>(key, &before, &message),
+        _ => check::<ChaskeyLTS>(key, &before, &message),
+    }
+});
@@ -0,0 +1,33 @@
+//! Splitting a message across any sequence of `write` calls gives the
+//! same tag as writing it all at once.
+
+#![no_main]
+
+use chaskey::core::Permutation;
+use chaskey::{Chaskey, Chaskey12, ChaskeyLTS, Digester};
+use libfuzzer_sys::fuzz_target;
+
+fn check<P: Permutation>(key: [u32; 4], message: &[u8], chunks: &[u8]) {
+    let mut whole: Digester<P> = Digester::new(key);
+    whole.write(message);
+
+    let mut split: Digester<P> = Digester::new(key);
+    let mut rest = message;
+    for &len in chunks {
+        let (chunk, tail) = rest.split_at((len as usize).min(rest.len()));
+        split.write(chunk);
+        rest = tail;
+    }
+    split.write(rest);
+
+    assert_eq!(whole.finish(), split.finish());
+}
+
+fuzz_target!(|input: (u8, [u32; 4], Vec<u8>, Vec<u8>)| {
+    let (variant, key, message, chunks) = input;
+    match variant % 3 {
+        0 => check::<Chaskey>(key, &message, &chunks),
+        1 => check::<Chaskey12>(key, &message, &chunks),
+        _ => check::<ChaskeyLTS>(key, &message, &chunks),
+    }
+});
@@ -0,0 +1,90 @@
+/*
+ * C interface to the `chaskey` Rust crate, from its `ffi` feature.  The
+ * `chaskey-ffi` crate in `ffi/` builds it as C libraries:
+ *
+ *     cargo build --release -p chaskey-ffi
+ *
+ * which produces `libchaskey_ffi.a` and `libchaskey_ffi.so` (or the
+ * platform's equivalents) in `target/release`.
+ *
+ * Keys, tags and cipher blocks are 16-byte arrays, with the same
+ * little-endian layout as the Rust crate's key files and
+ * `Tag::raw_bytes`.  Variants are given by their round counts:
+ * `CHASKEY_8`, `CHASKEY_12` or `CHASKEY_LTS`.
+ *
+ * Every function that can fail returns a `CHASKEY_*` status code,
+ * `CHASKEY_OK` (zero) on success.  Pointers may only be NULL where
+ * noted; a data pointer may also be NULL if its length is zero.
+ *
+ * This header is maintained by hand; keep it in step with
+ * `src/ffi.rs`.
+ */
+
+#ifndef CHASKEY_H
+#define CHASKEY_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Variants. */
+#define CHASKEY_8   8
+#define CHASKEY_12  12
+#define CHASKEY_LTS 16
+
+/* Status codes. */
+#define CHASKEY_OK              0
+#define CHASKEY_ERR_NULL       -1  /* a required pointer was NULL */
+#define CHASKEY_ERR_VARIANT    -2  /* not one of the CHASKEY_* variants */
+#define CHASKEY_ERR_TAG_LENGTH -3  /* a tag length outside 1 to 16 */
+#define CHASKEY_ERR_VERIFY     -4  /* the tag does not match */
+
+/* An incremental MAC computation.  Opaque; holds a copy of the key. */
+typedef struct chaskey_ctx chaskey_ctx;
+
+/*
+ * Allocate a context for the given variant and key, and store it in
+ * `*ctx`.  Release it with `chaskey_free`.
+ */
+int chaskey_new(chaskey_ctx **ctx, int variant, const uint8_t key[16]);
+
+/* Wipe and release a context.  `ctx` may be NULL. */
+void chaskey_free(chaskey_ctx *ctx);
+
+/* Start a new message with the same key. */
+int chaskey_reset(chaskey_ctx *ctx);
+
+/* Append `len` bytes to the message. */
+int chaskey_update(chaskey_ctx *ctx, const uint8_t *data, size_t len);
+
+/*
+ * Write the first `tag_len` (1 to 16) bytes of the message's tag to
+ * `tag`.  The context is not changed, so more data may be appended
+ * afterwards.
+ */
+int chaskey_final(const chaskey_ctx *ctx, uint8_t *tag, size_t tag_len);
+
+/*
+ * Check, in constant time, that `tag` is the first `tag_len` (1 to
+ * 16) bytes of the message's tag.  Returns `CHASKEY_OK` if so and
+ * `CHASKEY_ERR_VERIFY` if not.
+ */
+int chaskey_verify(const chaskey_ctx *ctx, const uint8_t *tag, size_t tag_len);
+
+/* One-shot MAC: `chaskey_new`, `chaskey_update` and `chaskey_final`. */
+int chaskey_mac(int variant, const uint8_t key[16],
+                const uint8_t *data, size_t len,
+                uint8_t *tag, size_t tag_len);
+
+/* Encrypt or decrypt one block in place with the Chaskey cipher. */
+int chaskey_encrypt_block(int variant, const uint8_t key[16], uint8_t block[16]);
+int chaskey_decrypt_block(int variant, const uint8_t key[16], uint8_t block[16]);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* CHASKEY_H */
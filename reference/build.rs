@@ -0,0 +1,19 @@
+extern crate cc;
+
+fn main() {
+    println!("cargo:rerun-if-changed=../tests/reference/chaskey.c");
+    println!("cargo:rerun-if-changed=../tests/reference/chaskey.h");
+    cc::Build::new()
+        .file("../tests/reference/chaskey.c")
+        .include("../tests/reference")
+        .warnings(true)
+        .compile("chaskeyref");
+
+    println!("cargo:rerun-if-changed=../tests/ffi/test_vectors.c");
+    println!("cargo:rerun-if-changed=../include/chaskey.h");
+    cc::Build::new()
+        .file("../tests/ffi/test_vectors.c")
+        .include("../include")
+        .warnings(true)
+        .compile("chaskeyffitest");
+}
@@ -0,0 +1,212 @@
+//! Tests of the `chaskey` crate that involve C code, which this
+//! crate's build script compiles.
+//!
+//! ```text
+//! cargo test -p chaskey-reference
+//! ```
+//!
+//! The differential tests compare the tags of the reference C code in
+//! `tests/reference` with the crate's, for random keys and messages of
+//! random length in all three variants.  On a mismatch, QuickCheck
+//! shrinks the input and reports the smallest key and message it
+//! found that still disagree.  The personalization tests do the same
+//! for `Digester::with_personalization`, whose key derivation is
+//! rebuilt here from the reference MAC.
+//!
+//! The `ffi` test runs the C program in `tests/ffi`, which checks the
+//! crate's C interface (`include/chaskey.h`) against the official
+//! test vectors.
+
+extern crate chaskey;
+
+#[cfg(test)] extern crate quickcheck;
+#[cfg(test)] extern crate rand;
+
+mod ffi {
+    extern "C" {
+        /// From `tests/ffi/test_vectors.c`: the number of failed checks.
+        #[cfg(test)]
+        pub fn chaskey_ffi_test() -> ::std::os::raw::c_int;
+
+        pub fn chaskey_ref_subkeys(k1: *mut u32, k2: *mut u32, k: *const u32);
+        pub fn chaskey_ref_mac(tag: *mut u8, taglen: u32,
+                               m: *const u8, mlen: u32,
+                               k: *const u32, k1: *const u32, k2: *const u32,
+                               rounds: i32);
+    }
+}
+
+/// Compute a full Chaskey tag with the reference C code, with the
+/// given number of permutation rounds (8, 12 or 16).
+///
+/// # Panics
+///
+/// If `message` is 4 GiB or longer.
+pub fn mac(rounds: i32, key: &[u32; 4], message: &[u8]) -> [u8; 16] {
+    assert!(message.len() <= u32::MAX as usize, "message too long for the reference code");
+    let mut k1 = [0u32; 4];
+    let mut k2 = [0u32; 4];
+    let mut tag = [0u8; 16];
+    unsafe {
+        ffi::chaskey_ref_subkeys(k1.as_mut_ptr(), k2.as_mut_ptr(), key.as_ptr());
+        ffi::chaskey_ref_mac(tag.as_mut_ptr(), 16,
+                             message.as_ptr(), message.len() as u32,
+                             key.as_ptr(), k1.as_ptr(), k2.as_ptr(),
+                             rounds);
+    }
+    tag
+}
+
+/// Derive the key of `Digester::with_personalization` with the
+/// reference C code: the context is padded by hand, so the C code
+/// sees a message of whole blocks, and the third subkey `K3 = 2·K2`
+/// goes where it expects `K1`.
+///
+/// # Panics
+///
+/// If `context` is 4 GiB or longer.
+pub fn personalized_key(rounds: i32, key: &[u32; 4], context: &[u8]) -> [u32; 4] {
+    let mut padded = context.to_vec();
+    padded.push(0x01);
+    while !padded.len().is_multiple_of(16) {
+        padded.push(0);
+    }
+    assert!(padded.len() <= u32::MAX as usize, "context too long for the reference code");
+    let mut k1 = [0u32; 4];
+    let mut k2 = [0u32; 4];
+    let mut k3 = [0u32; 4];
+    let mut scratch = [0u32; 4];
+    let mut tag = [0u8; 16];
+    unsafe {
+        ffi::chaskey_ref_subkeys(k1.as_mut_ptr(), k2.as_mut_ptr(), key.as_ptr());
+        ffi::chaskey_ref_subkeys(scratch.as_mut_ptr(), k3.as_mut_ptr(), k1.as_ptr());
+        ffi::chaskey_ref_mac(tag.as_mut_ptr(), 16,
+                             padded.as_ptr(), padded.len() as u32,
+                             key.as_ptr(), k3.as_ptr(), k2.as_ptr(),
+                             rounds);
+    }
+    let mut derived = [0u32; 4];
+    for (i, word) in derived.iter_mut().enumerate() {
+        *word = u32::from_le_bytes([tag[4 * i], tag[4 * i + 1], tag[4 * i + 2], tag[4 * i + 3]]);
+    }
+    derived
+}
+
+
+#[cfg(test)]
+mod tests {
+    use chaskey::{Chaskey, Chaskey12, ChaskeyLTS, Digester};
+    use chaskey::core::Permutation;
+    use quickcheck::{Arbitrary, Gen, QuickCheck, StdGen};
+    use rand;
+    use super::{ffi, mac, personalized_key};
+
+    /// A key with uniformly random words, which shrinks by zeroing
+    /// words.
+    #[derive(Clone, Debug)]
+    struct Key([u32; 4]);
+
+    impl Arbitrary for Key {
+        fn arbitrary<G: Gen>(g: &mut G) -> Key {
+            Key([g.gen(), g.gen(), g.gen(), g.gen()])
+        }
+
+        fn shrink(&self) -> Box<dyn Iterator<Item=Key>> {
+            let key = self.0;
+            Box::new((0..4).filter(move |&i| key[i] != 0).map(move |i| {
+                let mut smaller = key;
+                smaller[i] = 0;
+                Key(smaller)
+            }))
+        }
+    }
+
+    /// The round count to pass to the reference code for each variant.
+    trait Rounds { const ROUNDS: i32; }
+    impl Rounds for Chaskey { const ROUNDS: i32 = 8; }
+    impl Rounds for Chaskey12 { const ROUNDS: i32 = 12; }
+    impl Rounds for ChaskeyLTS { const ROUNDS: i32 = 16; }
+
+    /// Messages of up to this many bytes are generated.
+    const MAX_LENGTH: usize = 300;
+
+    fn differential<P: Permutation + Rounds>() {
+        fn prop<P: Permutation + Rounds>(key: Key, message: Vec<u8>) -> bool {
+            let mut digester: Digester<P> = Digester::new(key.0);
+            digester.write(&message);
+            digester.finish().raw_bytes() == mac(P::ROUNDS, &key.0, &message)
+        }
+        QuickCheck::new()
+            .tests(1000)
+            .gen(StdGen::new(rand::thread_rng(), MAX_LENGTH))
+            .quickcheck(prop::<P> as fn(Key, Vec<u8>) -> bool);
+    }
+
+    #[test]
+    fn differential_8() {
+        differential::<Chaskey>();
+    }
+
+    #[test]
+    fn differential_12() {
+        differential::<Chaskey12>();
+    }
+
+    #[test]
+    fn differential_lts() {
+        differential::<ChaskeyLTS>();
+    }
+
+    fn personalization<P: Permutation + Rounds>() {
+        fn prop<P: Permutation + Rounds>(key: Key, context: Vec<u8>, message: Vec<u8>) -> bool {
+            let mut digester: Digester<P> = Digester::with_personalization(key.0, &context);
+            digester.write(&message);
+            let derived = personalized_key(P::ROUNDS, &key.0, &context);
+            digester.finish().raw_bytes() == mac(P::ROUNDS, &derived, &message)
+        }
+        QuickCheck::new()
+            .tests(1000)
+            .gen(StdGen::new(rand::thread_rng(), MAX_LENGTH))
+            .quickcheck(prop::<P> as fn(Key, Vec<u8>, Vec<u8>) -> bool);
+    }
+
+    #[test]
+    fn personalization_8() {
+        personalization::<Chaskey>();
+    }
+
+    #[test]
+    fn personalization_12() {
+        personalization::<Chaskey12>();
+    }
+
+    #[test]
+    fn personalization_lts() {
+        personalization::<ChaskeyLTS>();
+    }
+
+    /// Every length up to a few blocks, since the block boundaries are
+    /// where the implementations are most likely to differ.
+    #[test]
+    fn all_short_lengths() {
+        let key = [0x833D3433, 0x009F389F, 0x2398E64F, 0x417ACF39];
+        let message: Vec<u8> = (0..=255u8).collect();
+        for len in 0..message.len() {
+            let msg = &message[..len];
+            let mut d8: Digester<Chaskey> = Digester::new(key);
+            let mut d12: Digester<Chaskey12> = Digester::new(key);
+            let mut d16: Digester<ChaskeyLTS> = Digester::new(key);
+            d8.write(msg);
+            d12.write(msg);
+            d16.write(msg);
+            assert_eq!(d8.finish().raw_bytes(), mac(8, &key, msg), "chaskey-8, length {}", len);
+            assert_eq!(d12.finish().raw_bytes(), mac(12, &key, msg), "chaskey-12, length {}", len);
+            assert_eq!(d16.finish().raw_bytes(), mac(16, &key, msg), "chaskey-lts, length {}", len);
+        }
+    }
+
+    #[test]
+    fn ffi() {
+        assert_eq!(unsafe { ffi::chaskey_ffi_test() }, 0, "see the failures printed above");
+    }
+}
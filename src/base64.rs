@@ -0,0 +1,91 @@
+//! URL-safe base64 (RFC 4648, section 5) without padding, for text
+//! formats such as tokens.
+//!
+//! Decoding is strict, so every byte string has exactly one encoding:
+//! padding, whitespace and characters outside the URL-safe alphabet
+//! are rejected, and so are unused bits that aren't zero.
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// Encode bytes.
+pub fn encode_url(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let mut group = [0u8; 3];
+        group[..chunk.len()].copy_from_slice(chunk);
+        let n = (group[0] as u32) << 16 | (group[1] as u32) << 8 | group[2] as u32;
+        for i in 0..chunk.len() + 1 {
+            out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+        }
+    }
+    out
+}
+
+/// Decode a string, or return `None` if it isn't the encoding of
+/// any byte string.
+pub fn decode_url(s: &str) -> Option<Vec<u8>> {
+    if s.len() % 4 == 1 {
+        return None;
+    }
+    let mut out = Vec::with_capacity(s.len() / 4 * 3 + 2);
+    for chunk in s.as_bytes().chunks(4) {
+        let mut n = 0u32;
+        for (i, &c) in chunk.iter().enumerate() {
+            n |= value(c)? << (18 - 6 * i);
+        }
+        let bytes = [(n >> 16) as u8, (n >> 8) as u8, n as u8];
+        let len = chunk.len() - 1;
+        if bytes[len..].iter().any(|&b| b != 0) {
+            return None;
+        }
+        out.extend_from_slice(&bytes[..len]);
+    }
+    Some(out)
+}
+
+fn value(c: u8) -> Option<u32> {
+    match c {
+        b'A'..=b'Z' => Some((c - b'A') as u32),
+        b'a'..=b'z' => Some((c - b'a') as u32 + 26),
+        b'0'..=b'9' => Some((c - b'0') as u32 + 52),
+        b'-' => Some(62),
+        b'_' => Some(63),
+        _ => None
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use quickcheck::quickcheck;
+    use super::{decode_url, encode_url};
+
+    #[test]
+    fn rfc4648_vectors() {
+        let vectors = [("", ""), ("f", "Zg"), ("fo", "Zm8"), ("foo", "Zm9v"),
+                       ("foob", "Zm9vYg"), ("fooba", "Zm9vYmE"), ("foobar", "Zm9vYmFy")];
+        for &(plain, encoded) in vectors.iter() {
+            assert_eq!(encode_url(plain.as_bytes()), encoded);
+            assert_eq!(decode_url(encoded), Some(plain.as_bytes().to_vec()));
+        }
+        assert_eq!(encode_url(&[0xfb, 0xff, 0xbf]), "-_-_");
+    }
+
+    #[test]
+    fn round_trip() {
+        fn prop(bytes: Vec<u8>) -> bool {
+            decode_url(&encode_url(&bytes)) == Some(bytes)
+        }
+        quickcheck(prop as fn(Vec<u8>) -> bool);
+    }
+
+    #[test]
+    fn errors() {
+        for s in ["Zg==", "Zm9v\n", "+/", "Z", "Zm9vY", "Zh", "Zm9", "Zm8 "].iter() {
+            assert_eq!(decode_url(s), None, "{:?}", s);
+        }
+        // "Zh" and "Zm9" have non-zero unused bits; these don't.
+        assert!(decode_url("Zg").is_some());
+        assert!(decode_url("Zm8").is_some());
+    }
+}
@@ -0,0 +1,302 @@
+//! The `encrypt` and `decrypt` subcommands: authenticated encryption
+//! of files with the Chaskey block cipher.
+//!
+//! An encrypted file consists of:
+//!
+//! | Offset  | Length | Contents                                        |
+//! |---------|--------|-------------------------------------------------|
+//! | 0       | 7      | The magic string `CHASKEY`                      |
+//! | 7       | 1      | Format version: 1                               |
+//! | 8       | 1      | Variant: 8, 12 or 16 (for Chaskey-LTS)          |
+//! | 9       | 1      | Mode: 1 (CTR encryption, then Chaskey MAC)      |
+//! | 10      | 16     | Nonce                                           |
+//! | 26      | n      | Ciphertext                                      |
+//! | 26 + n  | 16     | Tag                                             |
+//!
+//! Two subkeys are derived from the key `K` with the block cipher:
+//! the encryption key is `E(K, 1)` and the MAC key is `E(K, 2)`,
+//! where the numbers are read as 128-bit little-endian blocks.  The
+//! plaintext is encrypted with `chaskey::cipher::ctr` under the
+//! encryption key and the nonce, and the tag is the Chaskey MAC,
+//! under the MAC key, of everything that precedes it in the file.
+//!
+//! Files are processed in memory.  `decrypt` checks the tag before
+//! decrypting anything, and writes no output at all if it is wrong.
+
+use chaskey::{Chaskey, Chaskey12, ChaskeyLTS, Digester, Tag, Variant};
+use chaskey::cipher::{self, Permutation};
+use chaskey::keyfile::KeyFile;
+use rand::{OsRng, Rng};
+use std::fmt;
+use zeroize::Zeroizing;
+use super::{read_input, read_key_file, value, write_output, Failure};
+
+
+pub const ENCRYPT_USAGE: &str = "\
+Usage: chaskey encrypt --key-file PATH [OPTIONS] [FILE]
+
+Encrypt and authenticate FILE (or standard input).
+
+Options:
+  -k, --key-file PATH    the key file to use (required)
+  -o, --output PATH      write to PATH instead of standard output
+  -h, --help             print this message
+";
+
+pub const DECRYPT_USAGE: &str = "\
+Usage: chaskey decrypt --key-file PATH [OPTIONS] [FILE]
+
+Authenticate and decrypt FILE (or standard input).  Nothing is
+written unless the file is authentic.
+
+Options:
+  -k, --key-file PATH    the key file to use (required)
+  -o, --output PATH      write to PATH instead of standard output
+  -h, --help             print this message
+";
+
+const MAGIC: &[u8; 7] = b"CHASKEY";
+const VERSION: u8 = 1;
+const MODE_CTR_MAC: u8 = 1;
+const HEADER_LEN: usize = 26;
+const TAG_LEN: usize = 16;
+
+
+struct Options {
+    key_file: String,
+    output: Option<String>,
+    input: Option<String>
+}
+
+fn parse_args<I: Iterator<Item=String>>(usage: &'static str, mut args: I)
+                                        -> Result<Options, Failure> {
+    let mut key_file = None;
+    let mut output = None;
+    let mut input = None;
+    while let Some(arg) = args.next() {
+        match &*arg {
+            "-k" | "--key-file" => key_file = Some(value(usage, &arg, args.next())?),
+            "-o" | "--output" => output = Some(value(usage, &arg, args.next())?),
+            _ if arg.len() > 1 && arg.starts_with('-') => {
+                return Err(Failure::usage(usage, format!("unknown option `{}`", arg)));
+            }
+            _ if input.is_none() => input = Some(arg),
+            _ => return Err(Failure::usage(usage, format!("unexpected argument `{}`", arg)))
+        }
+    }
+    Ok(Options {
+        key_file: key_file.ok_or_else(|| {
+            Failure::usage(usage, "option `--key-file` is required".to_string())
+        })?,
+        output,
+        input
+    })
+}
+
+pub fn encrypt<I: Iterator<Item=String>>(args: I) -> Result<(), Failure> {
+    let opts = parse_args(ENCRYPT_USAGE, args)?;
+    let key = read_key_file(&opts.key_file)?;
+    let plaintext = read_input(opts.input.as_ref().map(|s| &s[..]))?;
+    let mut rng = OsRng::new().map_err(|e| format!("can't open the OS RNG: {}", e))?;
+    let sealed = seal(&key, rng.gen(), &plaintext);
+    write_output(opts.output.as_ref().map(|s| &s[..]), &sealed)
+}
+
+pub fn decrypt<I: Iterator<Item=String>>(args: I) -> Result<(), Failure> {
+    let opts = parse_args(DECRYPT_USAGE, args)?;
+    let key = read_key_file(&opts.key_file)?;
+    let sealed = read_input(opts.input.as_ref().map(|s| &s[..]))?;
+    let plaintext = open(&key, &sealed).map_err(|e| e.to_string())?;
+    write_output(opts.output.as_ref().map(|s| &s[..]), &plaintext)
+}
+
+
+/// Encrypt and authenticate `plaintext`.
+fn seal(key: &KeyFile, nonce: [u32; 4], plaintext: &[u8]) -> Vec<u8> {
+    match key.variant() {
+        Variant::Chaskey => seal_with::<Chaskey>(key, nonce, plaintext),
+        Variant::Chaskey12 => seal_with::<Chaskey12>(key, nonce, plaintext),
+        Variant::ChaskeyLTS => seal_with::<ChaskeyLTS>(key, nonce, plaintext)
+    }
+}
+
+/// Authenticate and decrypt the output of `seal`.
+fn open(key: &KeyFile, sealed: &[u8]) -> Result<Vec<u8>, OpenError> {
+    if sealed.len() < HEADER_LEN + TAG_LEN {
+        return Err(OpenError::Truncated);
+    }
+    if &sealed[0..7] != MAGIC {
+        return Err(OpenError::BadMagic);
+    }
+    if sealed[7] != VERSION {
+        return Err(OpenError::UnsupportedVersion(sealed[7]));
+    }
+    let variant = match sealed[8] {
+        8 => Variant::Chaskey,
+        12 => Variant::Chaskey12,
+        16 => Variant::ChaskeyLTS,
+        other => return Err(OpenError::UnknownVariant(other))
+    };
+    if variant != key.variant() {
+        return Err(OpenError::VariantMismatch(variant, key.variant()));
+    }
+    if sealed[9] != MODE_CTR_MAC {
+        return Err(OpenError::UnsupportedMode(sealed[9]));
+    }
+    match variant {
+        Variant::Chaskey => open_with::<Chaskey>(key, sealed),
+        Variant::Chaskey12 => open_with::<Chaskey12>(key, sealed),
+        Variant::ChaskeyLTS => open_with::<ChaskeyLTS>(key, sealed)
+    }
+}
+
+/// Derive the encryption and MAC subkeys from a key.  They are wiped
+/// when dropped.
+fn subkeys<P: Permutation>(key: &[u32; 4]) -> (Zeroizing<[u32; 4]>, Zeroizing<[u32; 4]>) {
+    let mut enc_key = Zeroizing::new([1, 0, 0, 0]);
+    let mut mac_key = Zeroizing::new([2, 0, 0, 0]);
+    cipher::encrypt::<P>(&mut enc_key, key);
+    cipher::encrypt::<P>(&mut mac_key, key);
+    (enc_key, mac_key)
+}
+
+fn mac<P: Permutation>(mac_key: &[u32; 4], data: &[u8]) -> Tag {
+    let mut mac: Digester<P> = Digester::new(*mac_key);
+    mac.write(data);
+    mac.finish()
+}
+
+fn seal_with<P: Permutation>(key: &KeyFile, nonce: [u32; 4], plaintext: &[u8]) -> Vec<u8> {
+    let (enc_key, mac_key) = subkeys::<P>(key.key());
+    let mut sealed = Vec::with_capacity(HEADER_LEN + plaintext.len() + TAG_LEN);
+    sealed.extend_from_slice(MAGIC);
+    sealed.push(VERSION);
+    sealed.push(variant_byte(key.variant()));
+    sealed.push(MODE_CTR_MAC);
+    for word in nonce.iter() {
+        sealed.extend_from_slice(&word.to_le_bytes());
+    }
+    sealed.extend_from_slice(plaintext);
+    cipher::ctr::<P>(&enc_key, &nonce, &mut sealed[HEADER_LEN..]);
+    let tag = mac::<P>(&mac_key, &sealed);
+    sealed.extend_from_slice(&tag.raw_bytes());
+    sealed
+}
+
+fn open_with<P: Permutation>(key: &KeyFile, sealed: &[u8]) -> Result<Vec<u8>, OpenError> {
+    let (enc_key, mac_key) = subkeys::<P>(key.key());
+    let (body, tag) = sealed.split_at(sealed.len() - TAG_LEN);
+    let mut expected = [0u32; 4];
+    for (word, chunk) in expected.iter_mut().zip(tag.chunks(4)) {
+        *word = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+    }
+    // `Tag`'s equality is constant-time.
+    if mac::<P>(&mac_key, body) != Tag::new(expected) {
+        return Err(OpenError::AuthenticationFailed);
+    }
+    let mut nonce = [0u32; 4];
+    for (word, chunk) in nonce.iter_mut().zip(body[10..HEADER_LEN].chunks(4)) {
+        *word = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+    }
+    let mut plaintext = body[HEADER_LEN..].to_vec();
+    cipher::ctr::<P>(&enc_key, &nonce, &mut plaintext);
+    Ok(plaintext)
+}
+
+fn variant_byte(variant: Variant) -> u8 {
+    match variant {
+        Variant::Chaskey => 8,
+        Variant::Chaskey12 => 12,
+        Variant::ChaskeyLTS => 16
+    }
+}
+
+
+/// The reasons `decrypt` can refuse a file.
+#[derive(Debug, PartialEq, Eq)]
+enum OpenError {
+    Truncated,
+    BadMagic,
+    UnsupportedVersion(u8),
+    UnknownVariant(u8),
+    /// The variant in the file, and the one in the key file.
+    VariantMismatch(Variant, Variant),
+    UnsupportedMode(u8),
+    AuthenticationFailed
+}
+
+impl fmt::Display for OpenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            OpenError::Truncated =>
+                write!(f, "not an encrypted file (too short)"),
+            OpenError::BadMagic =>
+                write!(f, "not an encrypted file (bad magic string)"),
+            OpenError::UnsupportedVersion(v) =>
+                write!(f, "unsupported format version {}", v),
+            OpenError::UnknownVariant(v) =>
+                write!(f, "unknown variant {}", v),
+            OpenError::VariantMismatch(file, key) =>
+                write!(f, "file was encrypted with {}, but the key is for {}", file, key),
+            OpenError::UnsupportedMode(m) =>
+                write!(f, "unsupported mode {}", m),
+            OpenError::AuthenticationFailed =>
+                write!(f, "authentication failed; the file is corrupted, \
+                           or the key is wrong")
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use chaskey::Variant;
+    use chaskey::keyfile::KeyFile;
+    use super::{open, seal, OpenError, HEADER_LEN, TAG_LEN};
+
+    const KEY: [u32; 4] = [0x833D3433, 0x009F389F, 0x2398E64F, 0x417ACF39];
+    const NONCE: [u32; 4] = [1, 2, 3, 4];
+
+    fn key(variant: Variant) -> KeyFile {
+        KeyFile::new(variant, KEY, None).unwrap()
+    }
+
+    #[test]
+    fn round_trip() {
+        for &variant in [Variant::Chaskey, Variant::Chaskey12, Variant::ChaskeyLTS].iter() {
+            for len in [0, 1, 15, 16, 17, 100].iter() {
+                let plaintext: Vec<u8> = (0..*len as u8).collect();
+                let sealed = seal(&key(variant), NONCE, &plaintext);
+                assert_eq!(sealed.len(), HEADER_LEN + plaintext.len() + TAG_LEN);
+                assert_eq!(open(&key(variant), &sealed), Ok(plaintext));
+            }
+        }
+    }
+
+    #[test]
+    fn header() {
+        let sealed = seal(&key(Variant::Chaskey12), NONCE, b"secret");
+        assert_eq!(&sealed[..10], b"CHASKEY\x01\x0c\x01");
+        assert_eq!(&sealed[10..14], &[1, 0, 0, 0]);
+        assert!(&sealed[HEADER_LEN..HEADER_LEN + 6] != b"secret");
+    }
+
+    #[test]
+    fn tampering() {
+        let sealed = seal(&key(Variant::Chaskey), NONCE, b"attack at dawn");
+        for i in 0..sealed.len() {
+            let mut tampered = sealed.clone();
+            tampered[i] ^= 0x01;
+            assert!(open(&key(Variant::Chaskey), &tampered).is_err(), "byte {}", i);
+        }
+        assert_eq!(open(&key(Variant::Chaskey), &sealed[..sealed.len() - 1]),
+                   Err(OpenError::AuthenticationFailed));
+        assert_eq!(open(&key(Variant::Chaskey), &sealed[..HEADER_LEN]),
+                   Err(OpenError::Truncated));
+
+        let other = KeyFile::new(Variant::Chaskey, [1, 2, 3, 4], None).unwrap();
+        assert_eq!(open(&other, &sealed), Err(OpenError::AuthenticationFailed));
+        assert_eq!(open(&key(Variant::ChaskeyLTS), &sealed),
+                   Err(OpenError::VariantMismatch(Variant::Chaskey, Variant::ChaskeyLTS)));
+    }
+}
@@ -0,0 +1,138 @@
+//! The `keygen` subcommand: generate a new key file.
+
+use chaskey::Variant;
+use chaskey::keyfile::KeyFile;
+use rand::{OsRng, Rng};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use super::{value, Failure};
+
+
+pub const USAGE: &str = "\
+Usage: chaskey keygen [OPTIONS]
+
+Generate a new random key, and write it in the Chaskey key file
+format.  The file is created readable and writable by its owner only.
+
+Options:
+  -a, --algorithm ALG    Chaskey variant: 8, 12 or lts (default: 8)
+  -i, --id ID            key identifier to record in the file
+  -o, --output PATH      write the key file to PATH instead of
+                         standard output
+  -f, --force            overwrite PATH if it already exists
+  -h, --help             print this message
+";
+
+
+struct Options {
+    variant: Variant,
+    id: Option<String>,
+    output: Option<String>,
+    force: bool
+}
+
+fn parse_args<I: Iterator<Item=String>>(mut args: I) -> Result<Options, Failure> {
+    let usage = USAGE;
+    let mut opts = Options {
+        variant: Variant::Chaskey,
+        id: None,
+        output: None,
+        force: false
+    };
+    while let Some(arg) = args.next() {
+        match &*arg {
+            "-a" | "--algorithm" => {
+                let name = value(usage, &arg, args.next())?;
+                opts.variant = name.parse().map_err(|_| {
+                    Failure::usage(usage, format!("unknown algorithm `{}` \
+                                                   (expected 8, 12 or lts)", name))
+                })?;
+            }
+            "-i" | "--id" => opts.id = Some(value(usage, &arg, args.next())?),
+            "-o" | "--output" => opts.output = Some(value(usage, &arg, args.next())?),
+            "-f" | "--force" => opts.force = true,
+            _ => return Err(Failure::usage(usage, format!("unexpected argument `{}`", arg)))
+        }
+    }
+    Ok(opts)
+}
+
+pub fn run<I: Iterator<Item=String>>(args: I) -> Result<(), Failure> {
+    let opts = parse_args(args)?;
+    let mut rng = OsRng::new().map_err(|e| format!("can't open the OS RNG: {}", e))?;
+    let file = KeyFile::new(opts.variant, rng.gen(), opts.id.as_ref().map(|id| &id[..]))
+        .map_err(|e| e.to_string())?;
+    match opts.output {
+        None => {
+            io::stdout().write_all(file.to_string().as_bytes())
+                .map_err(|e| format!("can't write the key: {}", e))?;
+        }
+        Some(ref path) => {
+            let mut out = create_private(path, opts.force)
+                .map_err(|e| format!("{}: {}", path, e))?;
+            out.write_all(file.to_string().as_bytes())
+                .and_then(|_| out.sync_all())
+                .map_err(|e| format!("{}: {}", path, e))?;
+        }
+    }
+    Ok(())
+}
+
+/// Create a file that only its owner can read and write.  Unless
+/// `force` is set, fail if the file already exists.
+#[cfg(unix)]
+fn create_private(path: &str, force: bool) -> io::Result<File> {
+    use std::fs::{self, Permissions};
+    use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+
+    let file = OpenOptions::new()
+        .write(true)
+        .create_new(!force)
+        .create(force)
+        .truncate(force)
+        .mode(0o600)
+        .open(path)?;
+    // The mode only applies to newly created files, so an existing
+    // file we overwrite has to be fixed up.
+    fs::set_permissions(path, Permissions::from_mode(0o600))?;
+    Ok(file)
+}
+
+#[cfg(not(unix))]
+fn create_private(path: &str, force: bool) -> io::Result<File> {
+    OpenOptions::new()
+        .write(true)
+        .create_new(!force)
+        .create(force)
+        .truncate(force)
+        .open(path)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use chaskey::Variant;
+    use super::parse_args;
+
+    fn args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn options() {
+        let opts = parse_args(args("-a lts --id k7 -o key.txt -f").into_iter())
+            .ok().unwrap();
+        assert_eq!(opts.variant, Variant::ChaskeyLTS);
+        assert_eq!(opts.id, Some("k7".to_string()));
+        assert_eq!(opts.output, Some("key.txt".to_string()));
+        assert!(opts.force);
+
+        let opts = parse_args(args("").into_iter()).ok().unwrap();
+        assert_eq!(opts.variant, Variant::Chaskey);
+        assert!(opts.output.is_none() && !opts.force);
+
+        assert!(parse_args(args("-a 9").into_iter()).is_err());
+        assert!(parse_args(args("--id").into_iter()).is_err());
+        assert!(parse_args(args("extra").into_iter()).is_err());
+    }
+}
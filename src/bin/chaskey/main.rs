@@ -0,0 +1,154 @@
+//! Chaskey key management and utilities.
+//!
+//! ```text
+//! chaskey keygen [OPTIONS]
+//! chaskey encrypt --key-file PATH [OPTIONS] [FILE]
+//! chaskey decrypt --key-file PATH [OPTIONS] [FILE]
+//! ```
+//!
+//! The `keygen` subcommand writes a new key, drawn from the operating
+//! system's random number generator, in the format of the
+//! `chaskey::keyfile` module.  The `encrypt` and `decrypt`
+//! subcommands protect files with the Chaskey block cipher in an
+//! authenticated mode; see the `crypt` module for the file format.
+
+extern crate chaskey;
+extern crate rand;
+extern crate zeroize;
+
+mod crypt;
+mod keygen;
+
+use chaskey::keyfile::KeyFile;
+use std::env;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::process;
+
+
+const USAGE: &str = "\
+Usage: chaskey <COMMAND> [OPTIONS]
+
+Commands:
+  keygen    generate a new key file
+  encrypt   encrypt and authenticate a file
+  decrypt   authenticate and decrypt a file
+
+Run `chaskey <COMMAND> --help` for the options of each command.
+";
+
+/// Exit status for usage errors.  (Other failures exit with 1.)
+const EXIT_USAGE: i32 = 2;
+
+
+/// An error, and the usage message to show with it (if any).
+struct Failure {
+    message: String,
+    usage: Option<&'static str>
+}
+
+impl Failure {
+    fn usage(usage: &'static str, message: String) -> Failure {
+        Failure { message, usage: Some(usage) }
+    }
+}
+
+impl From<String> for Failure {
+    fn from(message: String) -> Failure {
+        Failure { message, usage: None }
+    }
+}
+
+/// A subcommand's entry point, which takes the arguments after the
+/// command name.
+type Command = fn(Vec<String>) -> Result<(), Failure>;
+
+fn value(usage: &'static str, option: &str, value: Option<String>) -> Result<String, Failure> {
+    value.ok_or_else(|| Failure::usage(usage, format!("option `{}` requires a value", option)))
+}
+
+fn read_key_file(path: &str) -> Result<KeyFile, Failure> {
+    let mut contents = String::new();
+    File::open(path)
+        .and_then(|mut f| f.read_to_string(&mut contents))
+        .map_err(|e| format!("{}: {}", path, e))?;
+    Ok(contents.parse().map_err(|e| format!("{}: {}", path, e))?)
+}
+
+/// Read all of a file, or of standard input if `path` is `None` or
+/// `-`.
+fn read_input(path: Option<&str>) -> Result<Vec<u8>, Failure> {
+    let mut data = Vec::new();
+    match path {
+        None | Some("-") => {
+            io::stdin().read_to_end(&mut data)
+                .map_err(|e| format!("can't read standard input: {}", e))?;
+        }
+        Some(path) => {
+            File::open(path)
+                .and_then(|mut f| f.read_to_end(&mut data))
+                .map_err(|e| format!("{}: {}", path, e))?;
+        }
+    }
+    Ok(data)
+}
+
+/// Write `data` to a file, or to standard output if `path` is `None`
+/// or `-`.
+fn write_output(path: Option<&str>, data: &[u8]) -> Result<(), Failure> {
+    match path {
+        None | Some("-") => {
+            let stdout = io::stdout();
+            let mut out = stdout.lock();
+            out.write_all(data)
+                .and_then(|_| out.flush())
+                .map_err(|e| format!("can't write standard output: {}", e))?;
+        }
+        Some(path) => {
+            File::create(path)
+                .and_then(|mut f| f.write_all(data))
+                .map_err(|e| format!("{}: {}", path, e))?;
+        }
+    }
+    Ok(())
+}
+
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let command = args.next();
+    let rest: Vec<String> = args.collect();
+    let wants_help = rest.iter().any(|a| a == "-h" || a == "--help");
+
+    let (usage, run): (&str, Command) =
+        match command.as_ref().map(|c| &c[..]) {
+            Some("keygen") => (keygen::USAGE, |args| keygen::run(args.into_iter())),
+            Some("encrypt") => (crypt::ENCRYPT_USAGE, |args| crypt::encrypt(args.into_iter())),
+            Some("decrypt") => (crypt::DECRYPT_USAGE, |args| crypt::decrypt(args.into_iter())),
+            Some("-h") | Some("--help") | Some("help") => {
+                print!("{}", USAGE);
+                return;
+            }
+            Some(other) => fail(Failure::usage(USAGE, format!("unknown command `{}`", other))),
+            None => fail(Failure::usage(USAGE, "no command given".to_string()))
+        };
+
+    if wants_help {
+        print!("{}", usage);
+        return;
+    }
+    if let Err(failure) = run(rest) {
+        fail(failure);
+    }
+}
+
+fn fail(failure: Failure) -> ! {
+    eprintln!("chaskey: {}", failure.message);
+    match failure.usage {
+        Some(usage) => {
+            eprintln!("\n{}", usage);
+            process::exit(EXIT_USAGE);
+        }
+        None => process::exit(1)
+    }
+}
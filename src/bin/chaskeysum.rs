@@ -0,0 +1,361 @@
+//! Compute and check Chaskey tags of files, in the style of
+//! `sha256sum`.
+//!
+//! ```text
+//! chaskeysum [OPTIONS] [FILE]...
+//! chaskeysum --check [OPTIONS] [FILE]...
+//! ```
+//!
+//! With no `FILE`, or when `FILE` is `-`, standard input is read.
+//! The key is read from the key file given with `--key-file` (see
+//! the `chaskey::keyfile` module), or else from the `CHASKEY_KEY`
+//! environment variable, as 32 hexadecimal digits.
+
+extern crate chaskey;
+
+use chaskey::{Chaskey, Chaskey12, ChaskeyLTS, Digester, Variant};
+use chaskey::core::Permutation;
+use chaskey::keyfile::{self, KeyFile};
+use chaskey::subtle::ConstantTimeEq;
+use chaskey::hex::{FromHex, ToHex};
+use std::env;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::process;
+
+
+const USAGE: &str = "\
+Usage: chaskeysum [OPTIONS] [FILE]...
+       chaskeysum --check [OPTIONS] [FILE]...
+
+Print or check Chaskey tags.  With no FILE, or when FILE is -, read
+standard input.
+
+Options:
+  -k, --key-file PATH    read the key from the key file PATH
+                         (default: the CHASKEY_KEY environment
+                         variable, as 32 hexadecimal digits)
+  -a, --algorithm ALG    Chaskey variant: 8, 12 or lts (default: the
+                         key file's variant, or else 8)
+  -t, --truncate BITS    truncate tags to BITS bits, a multiple of 8
+                         between 8 and 128 (default: 128)
+  -c, --check            read tags from the FILEs and check them
+  -q, --quiet            when checking, don't print OK lines
+  -h, --help             print this message
+";
+
+/// The environment variable the key is read from when no key file
+/// is given.
+const KEY_VAR: &str = "CHASKEY_KEY";
+
+/// Exit status for usage errors.  (Failed checks and I/O errors
+/// exit with 1.)
+const EXIT_USAGE: i32 = 2;
+
+
+struct Options {
+    key_file: Option<String>,
+    variant: Option<Variant>,
+    tag_bytes: usize,
+    check: bool,
+    quiet: bool,
+    files: Vec<String>
+}
+
+fn parse_args<I: Iterator<Item=String>>(mut args: I) -> Result<Options, String> {
+    let mut opts = Options {
+        key_file: None,
+        variant: None,
+        tag_bytes: 16,
+        check: false,
+        quiet: false,
+        files: Vec::new()
+    };
+    while let Some(arg) = args.next() {
+        match &*arg {
+            "-k" | "--key-file" => {
+                opts.key_file = Some(value(&arg, args.next())?);
+            }
+            "-a" | "--algorithm" => {
+                let name = value(&arg, args.next())?;
+                opts.variant = Some(name.parse().map_err(|_| {
+                    format!("unknown algorithm `{}` (expected 8, 12 or lts)", name)
+                })?);
+            }
+            "-t" | "--truncate" => {
+                opts.tag_bytes = parse_truncation(&value(&arg, args.next())?)?;
+            }
+            "-c" | "--check" => opts.check = true,
+            "-q" | "--quiet" => opts.quiet = true,
+            "--" => {
+                opts.files.extend(args);
+                break;
+            }
+            _ if arg.len() > 1 && arg.starts_with('-') => {
+                return Err(format!("unknown option `{}`", arg));
+            }
+            _ => opts.files.push(arg)
+        }
+    }
+    if opts.files.is_empty() {
+        opts.files.push("-".to_string());
+    }
+    Ok(opts)
+}
+
+fn value(option: &str, value: Option<String>) -> Result<String, String> {
+    value.ok_or_else(|| format!("option `{}` requires a value", option))
+}
+
+/// Parse a truncation length in bits into a tag length in bytes.
+fn parse_truncation(s: &str) -> Result<usize, String> {
+    match s.parse::<usize>() {
+        Ok(bits) if bits % 8 == 0 && (8..=128).contains(&bits) => Ok(bits / 8),
+        _ => Err(format!("invalid truncation `{}` (expected a multiple \
+                          of 8 between 8 and 128)", s))
+    }
+}
+
+/// Read the key, and work out which variant to use with it.
+fn read_key(opts: &Options) -> Result<(Variant, [u32; 4]), String> {
+    match opts.key_file {
+        Some(ref path) => {
+            let mut contents = String::new();
+            File::open(path)
+                .and_then(|mut f| f.read_to_string(&mut contents))
+                .map_err(|e| format!("{}: {}", path, e))?;
+            let file: KeyFile = contents.parse().map_err(|e| format!("{}: {}", path, e))?;
+            match opts.variant {
+                Some(variant) if variant != file.variant() => {
+                    Err(format!("{}: key is for {}, not {}", path, file.variant(), variant))
+                }
+                _ => Ok((file.variant(), *file.key()))
+            }
+        }
+        None => {
+            let hex = env::var(KEY_VAR).map_err(|_| {
+                format!("no key given; use --key-file or set {}", KEY_VAR)
+            })?;
+            let key = keyfile::parse_key(&hex).map_err(|e| format!("{}: {}", KEY_VAR, e))?;
+            Ok((opts.variant.unwrap_or(Variant::Chaskey), key))
+        }
+    }
+}
+
+
+/// Compute the (untruncated) tag of everything `reader` produces.
+fn digest<P: Permutation, R: Read>(key: [u32; 4], mut reader: R) -> io::Result<[u8; 16]> {
+    let mut mac: Digester<P> = Digester::new(key);
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        match reader.read(&mut buf) {
+            Ok(0) => return Ok(mac.finish().raw_bytes()),
+            Ok(n) => mac.write(&buf[..n]),
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e)
+        }
+    }
+}
+
+fn digest_variant<R: Read>(variant: Variant, key: [u32; 4], reader: R) -> io::Result<[u8; 16]> {
+    match variant {
+        Variant::Chaskey => digest::<Chaskey, R>(key, reader),
+        Variant::Chaskey12 => digest::<Chaskey12, R>(key, reader),
+        Variant::ChaskeyLTS => digest::<ChaskeyLTS, R>(key, reader)
+    }
+}
+
+fn digest_file(variant: Variant, key: [u32; 4], path: &str) -> io::Result<[u8; 16]> {
+    if path == "-" {
+        let stdin = io::stdin();
+        let lock = stdin.lock();
+        digest_variant(variant, key, lock)
+    } else {
+        digest_variant(variant, key, File::open(path)?)
+    }
+}
+
+/// Split a check line of the form `TAG  FILE` into its parts.
+fn parse_check_line(line: &str) -> Option<(Vec<u8>, &str)> {
+    let (tag, file) = line.split_once(' ')?;
+    // `sha256sum` puts a `*` in front of the file name to mark
+    // binary mode; we accept (and ignore) it.
+    let file = file.strip_prefix(|c| c == ' ' || c == '*').unwrap_or(file);
+    match tag.from_hex() {
+        Ok(ref bytes) if bytes.is_empty() || bytes.len() > 16 => None,
+        Ok(bytes) if !file.is_empty() => Some((bytes, file)),
+        _ => None
+    }
+}
+
+
+fn sum(opts: &Options, variant: Variant, key: [u32; 4]) -> bool {
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let mut ok = true;
+    for path in opts.files.iter() {
+        match digest_file(variant, key, path) {
+            Ok(tag) => {
+                let _ = writeln!(out, "{}  {}", tag[..opts.tag_bytes].to_hex(), path);
+            }
+            Err(e) => {
+                eprintln!("chaskeysum: {}: {}", path, e);
+                ok = false;
+            }
+        }
+    }
+    ok
+}
+
+fn check(opts: &Options, variant: Variant, key: [u32; 4]) -> bool {
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let (mut failed, mut unreadable, mut malformed) = (0, 0, 0);
+
+    for list in opts.files.iter() {
+        let reader: Box<dyn BufRead> = if list == "-" {
+            Box::new(BufReader::new(io::stdin()))
+        } else {
+            match File::open(list) {
+                Ok(f) => Box::new(BufReader::new(f)),
+                Err(e) => {
+                    eprintln!("chaskeysum: {}: {}", list, e);
+                    unreadable += 1;
+                    continue;
+                }
+            }
+        };
+        for line in reader.lines() {
+            let line = match line {
+                Ok(line) => line,
+                Err(e) => {
+                    eprintln!("chaskeysum: {}: {}", list, e);
+                    unreadable += 1;
+                    break;
+                }
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            // A tag of the wrong length would be checked against a
+            // truncation the user didn't ask for.
+            let (expected, path) = match parse_check_line(&line) {
+                Some((tag, path)) if tag.len() == opts.tag_bytes => (tag, path),
+                _ => {
+                    malformed += 1;
+                    continue;
+                }
+            };
+            match digest_file(variant, key, path) {
+                Ok(tag) if bool::from(tag[..expected.len()].ct_eq(&expected)) => {
+                    if !opts.quiet {
+                        let _ = writeln!(out, "{}: OK", path);
+                    }
+                }
+                Ok(_) => {
+                    let _ = writeln!(out, "{}: FAILED", path);
+                    failed += 1;
+                }
+                Err(e) => {
+                    let _ = writeln!(out, "{}: FAILED open or read", path);
+                    eprintln!("chaskeysum: {}: {}", path, e);
+                    unreadable += 1;
+                }
+            }
+        }
+    }
+
+    if malformed > 0 {
+        eprintln!("chaskeysum: WARNING: {} line(s) are improperly formatted", malformed);
+    }
+    if unreadable > 0 {
+        eprintln!("chaskeysum: WARNING: {} file(s) could not be read", unreadable);
+    }
+    if failed > 0 {
+        eprintln!("chaskeysum: WARNING: {} computed tag(s) did NOT match", failed);
+    }
+    failed == 0 && unreadable == 0 && malformed == 0
+}
+
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.iter().any(|a| a == "-h" || a == "--help") {
+        print!("{}", USAGE);
+        return;
+    }
+    let opts = match parse_args(args.into_iter()) {
+        Ok(opts) => opts,
+        Err(e) => {
+            eprintln!("chaskeysum: {}\n\n{}", e, USAGE);
+            process::exit(EXIT_USAGE);
+        }
+    };
+    let (variant, key) = match read_key(&opts) {
+        Ok(key) => key,
+        Err(e) => {
+            eprintln!("chaskeysum: {}", e);
+            process::exit(EXIT_USAGE);
+        }
+    };
+    let ok = if opts.check {
+        check(&opts, variant, key)
+    } else {
+        sum(&opts, variant, key)
+    };
+    if !ok {
+        process::exit(1);
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY_HEX: &str = "33343d839f389f004fe6982339cf7a41";
+    const KEY: [u32; 4] = [0x833D3433, 0x009F389F, 0x2398E64F, 0x417ACF39];
+
+    fn args(s: &str) -> Result<Options, String> {
+        parse_args(s.split_whitespace().map(String::from))
+    }
+
+    #[test]
+    fn options() {
+        let opts = args("-a lts --truncate 64 -c a b").unwrap();
+        assert_eq!(opts.variant, Some(Variant::ChaskeyLTS));
+        assert_eq!(opts.tag_bytes, 8);
+        assert!(opts.check);
+        assert_eq!(opts.files, vec!["a", "b"]);
+
+        assert_eq!(args("").unwrap().files, vec!["-"]);
+        assert_eq!(args("-- -a").unwrap().files, vec!["-a"]);
+        assert!(args("-t 12").is_err());
+        assert!(args("-t 136").is_err());
+        assert!(args("-a 9").is_err());
+        assert!(args("-k").is_err());
+        assert!(args("--frobnicate").is_err());
+    }
+
+    #[test]
+    fn check_lines() {
+        assert_eq!(parse_check_line("00ff  some file"),
+                   Some((vec![0x00, 0xff], "some file")));
+        assert_eq!(parse_check_line("00ff *binary"),
+                   Some((vec![0x00, 0xff], "binary")));
+        assert_eq!(parse_check_line("00ff"), None);
+        assert_eq!(parse_check_line("0xff  file"), None);
+        assert_eq!(parse_check_line(&format!("{}00  file", KEY_HEX)), None);
+    }
+
+    /// The streaming digest must agree with the library's test
+    /// vectors (the 8-round tag of the 3-byte message `00 01 02`).
+    #[test]
+    fn digest_stream() {
+        let tag = digest_variant(Variant::Chaskey, KEY, &[0u8, 1, 2][..]).unwrap();
+        let expected = [0x1BDBB264u32, 0xA07680D8, 0x8E5B2AB8, 0x20660413];
+        for (i, word) in expected.iter().enumerate() {
+            assert_eq!(&tag[4 * i..4 * i + 4], &word.to_le_bytes());
+        }
+    }
+}
@@ -0,0 +1,500 @@
+//! A challenge–response protocol for authenticating devices that
+//! share a key with a verifier, optionally in both directions.
+//!
+//! ```text
+//! Verifier                                      Prover
+//!    challenge:    1 || Nv                  ->
+//!                                           <-  response:      2 || Np || Tp
+//!    (mutual only) confirmation: 3 || Tv    ->
+//! ```
+//!
+//! `Nv` and `Np` are 16-byte random nonces.  The tags are computed
+//! with a `StructuredDigester` over the fields
+//!
+//! ```text
+//! verifier id, prover id, Nv, Np, mutual
+//! ```
+//!
+//! (byte strings, then a `bool`), each role with its own key derived
+//! from the shared key with `Digester::with_personalization`:
+//! `Tp` with the context `"chaskey-challenge-v1 prover"` and `Tv` with
+//! `"chaskey-challenge-v1 verifier"`.  So neither side's tags can be
+//! passed off as the other's, and both sides must agree on the ids
+//! and on whether authentication is mutual.
+//!
+//! The `Verifier` and `Prover` state machines only take and return
+//! messages as bytes, and get randomness from the caller, so both
+//! ends can run in one process in tests.  Each runs the protocol
+//! once; any error leaves it failed.
+//!
+//! ```
+//! # extern crate chaskey;
+//! # extern crate rand;
+//! use chaskey::Chaskey12;
+//! use chaskey::challenge::{Prover, Verifier};
+//!
+//! # fn main() {
+//! let key = [1, 2, 3, 4];
+//! let mut rng = rand::thread_rng();
+//! let mut server: Verifier<Chaskey12> = Verifier::new(key, b"server", b"meter-17", true);
+//! let mut device: Prover<Chaskey12> = Prover::new(key, b"server", b"meter-17", true);
+//!
+//! let challenge = server.challenge(&mut rng).unwrap();
+//! let response = device.respond(&challenge, &mut rng).unwrap();
+//! let confirmation = server.verify(&response).unwrap().unwrap();
+//! device.confirm(&confirmation).unwrap();
+//! assert!(server.is_authenticated() && device.is_authenticated());
+//! # }
+//! ```
+
+use core::Permutation;
+use rand::Rng;
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use structured::StructuredDigester;
+use Tag;
+
+/// The personalization context of the prover's tags.
+const PROVER_CONTEXT: &[u8] = b"chaskey-challenge-v1 prover";
+
+/// The personalization context of the verifier's tags.
+const VERIFIER_CONTEXT: &[u8] = b"chaskey-challenge-v1 verifier";
+
+const CHALLENGE: u8 = 1;
+const RESPONSE: u8 = 2;
+const CONFIRMATION: u8 = 3;
+
+/// The length of the nonces, in bytes.
+pub const NONCE_LEN: usize = 16;
+
+
+/// What both ends know about a run of the protocol.
+struct Session<P> {
+    verifier_id: Vec<u8>,
+    prover_id: Vec<u8>,
+    mutual: bool,
+    prover_mac: StructuredDigester<P>,
+    verifier_mac: StructuredDigester<P>
+}
+
+impl<P: Permutation> Session<P> {
+    fn new(key: [u32; 4], verifier_id: &[u8], prover_id: &[u8], mutual: bool) -> Session<P> {
+        Session {
+            verifier_id: verifier_id.to_vec(),
+            prover_id: prover_id.to_vec(),
+            mutual,
+            prover_mac: StructuredDigester::with_personalization(key, PROVER_CONTEXT),
+            verifier_mac: StructuredDigester::with_personalization(key, VERIFIER_CONTEXT)
+        }
+    }
+
+    fn prover_tag(&mut self, nv: &[u8], np: &[u8]) -> Tag {
+        tag(&mut self.prover_mac, &self.verifier_id, &self.prover_id, nv, np, self.mutual)
+    }
+
+    fn verifier_tag(&mut self, nv: &[u8], np: &[u8]) -> Tag {
+        tag(&mut self.verifier_mac, &self.verifier_id, &self.prover_id, nv, np, self.mutual)
+    }
+}
+
+fn tag<P: Permutation>(mac: &mut StructuredDigester<P>, verifier_id: &[u8], prover_id: &[u8],
+                       nv: &[u8], np: &[u8], mutual: bool) -> Tag {
+    mac.reset();
+    mac.bytes(verifier_id);
+    mac.bytes(prover_id);
+    mac.bytes(nv);
+    mac.bytes(np);
+    mac.bool(mutual);
+    mac.finish()
+}
+
+/// The body of a message of type `kind` that should be `len` bytes
+/// long in all.
+fn body(message: &[u8], kind: u8, len: usize) -> Result<&[u8], ProtocolError> {
+    if message.len() != len || message[0] != kind {
+        return Err(ProtocolError::Malformed);
+    }
+    Ok(&message[1..])
+}
+
+
+/// The verifier's end: it sends the challenge, checks the response,
+/// and in mutual mode sends the confirmation.
+pub struct Verifier<P> {
+    session: Session<P>,
+    state: VerifierState
+}
+
+enum VerifierState {
+    Start,
+    AwaitingResponse([u8; NONCE_LEN]),
+    Authenticated,
+    Failed
+}
+
+impl<P: Permutation> Verifier<P> {
+    /// A verifier called `verifier_id` that will authenticate a
+    /// prover called `prover_id`, and also authenticate itself to
+    /// the prover if `mutual`.
+    pub fn new(key: [u32; 4], verifier_id: &[u8], prover_id: &[u8], mutual: bool) -> Verifier<P> {
+        Verifier { session: Session::new(key, verifier_id, prover_id, mutual), state: VerifierState::Start }
+    }
+
+    /// Start the protocol: make the challenge message.  `rng` should
+    /// be a cryptographically secure generator such as `OsRng`.
+    pub fn challenge<R: Rng>(&mut self, rng: &mut R) -> Result<Vec<u8>, ProtocolError> {
+        match self.state {
+            VerifierState::Start => (),
+            _ => return Err(self.fail(ProtocolError::UnexpectedMessage))
+        }
+        let mut nv = [0u8; NONCE_LEN];
+        rng.fill_bytes(&mut nv);
+        self.state = VerifierState::AwaitingResponse(nv);
+        let mut message = vec![CHALLENGE];
+        message.extend_from_slice(&nv);
+        Ok(message)
+    }
+
+    /// Check the prover's response.  On success the prover is
+    /// authenticated, and in mutual mode this returns the
+    /// confirmation message to send back.
+    pub fn verify(&mut self, response: &[u8]) -> Result<Option<Vec<u8>>, ProtocolError> {
+        let nv = match self.state {
+            VerifierState::AwaitingResponse(nv) => nv,
+            _ => return Err(self.fail(ProtocolError::UnexpectedMessage))
+        };
+        let body = match body(response, RESPONSE, 1 + NONCE_LEN + 16) {
+            Ok(body) => body,
+            Err(e) => return Err(self.fail(e))
+        };
+        let (np, received) = body.split_at(NONCE_LEN);
+        // `Tag`'s `==` is constant time.
+        if Tag::try_from(received).ok() != Some(self.session.prover_tag(&nv, np)) {
+            return Err(self.fail(ProtocolError::AuthenticationFailed));
+        }
+        self.state = VerifierState::Authenticated;
+        if !self.session.mutual {
+            return Ok(None);
+        }
+        let mut message = vec![CONFIRMATION];
+        message.extend_from_slice(&self.session.verifier_tag(&nv, np).raw_bytes());
+        Ok(Some(message))
+    }
+
+    /// Whether the prover has been authenticated.
+    pub fn is_authenticated(&self) -> bool {
+        matches!(self.state, VerifierState::Authenticated)
+    }
+
+    fn fail(&mut self, error: ProtocolError) -> ProtocolError {
+        self.state = VerifierState::Failed;
+        error
+    }
+}
+
+
+/// The prover's end: it answers the challenge, and in mutual mode
+/// checks the confirmation.
+pub struct Prover<P> {
+    session: Session<P>,
+    state: ProverState
+}
+
+enum ProverState {
+    Start,
+    AwaitingConfirmation([u8; NONCE_LEN], [u8; NONCE_LEN]),
+    Responded,
+    Authenticated,
+    Failed
+}
+
+impl<P: Permutation> Prover<P> {
+    /// A prover called `prover_id` that will authenticate itself to a
+    /// verifier called `verifier_id`, and also authenticate the
+    /// verifier if `mutual`.
+    pub fn new(key: [u32; 4], verifier_id: &[u8], prover_id: &[u8], mutual: bool) -> Prover<P> {
+        Prover { session: Session::new(key, verifier_id, prover_id, mutual), state: ProverState::Start }
+    }
+
+    /// Answer the verifier's challenge.  `rng` should be a
+    /// cryptographically secure generator such as `OsRng`.
+    pub fn respond<R: Rng>(&mut self, challenge: &[u8], rng: &mut R)
+                           -> Result<Vec<u8>, ProtocolError> {
+        match self.state {
+            ProverState::Start => (),
+            _ => return Err(self.fail(ProtocolError::UnexpectedMessage))
+        }
+        let body = match body(challenge, CHALLENGE, 1 + NONCE_LEN) {
+            Ok(body) => body,
+            Err(e) => return Err(self.fail(e))
+        };
+        let mut nv = [0u8; NONCE_LEN];
+        nv.copy_from_slice(body);
+        let mut np = [0u8; NONCE_LEN];
+        rng.fill_bytes(&mut np);
+
+        let mut message = vec![RESPONSE];
+        message.extend_from_slice(&np);
+        message.extend_from_slice(&self.session.prover_tag(&nv, &np).raw_bytes());
+        self.state = if self.session.mutual {
+            ProverState::AwaitingConfirmation(nv, np)
+        } else {
+            ProverState::Responded
+        };
+        Ok(message)
+    }
+
+    /// Check the verifier's confirmation, in mutual mode.  On success
+    /// the verifier is authenticated.
+    pub fn confirm(&mut self, confirmation: &[u8]) -> Result<(), ProtocolError> {
+        let (nv, np) = match self.state {
+            ProverState::AwaitingConfirmation(nv, np) => (nv, np),
+            _ => return Err(self.fail(ProtocolError::UnexpectedMessage))
+        };
+        let received = match body(confirmation, CONFIRMATION, 1 + 16) {
+            Ok(body) => body,
+            Err(e) => return Err(self.fail(e))
+        };
+        if Tag::try_from(received).ok() != Some(self.session.verifier_tag(&nv, &np)) {
+            return Err(self.fail(ProtocolError::AuthenticationFailed));
+        }
+        self.state = ProverState::Authenticated;
+        Ok(())
+    }
+
+    /// Whether the verifier has been authenticated, which only
+    /// happens in mutual mode.
+    pub fn is_authenticated(&self) -> bool {
+        matches!(self.state, ProverState::Authenticated)
+    }
+
+    fn fail(&mut self, error: ProtocolError) -> ProtocolError {
+        self.state = ProverState::Failed;
+        error
+    }
+}
+
+
+/// Errors from running the protocol.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProtocolError {
+    /// The message has the wrong type or length.
+    Malformed,
+    /// The message (or call) is not the next step of the protocol,
+    /// or the protocol has already finished or failed.
+    UnexpectedMessage,
+    /// The other side's tag doesn't match.
+    AuthenticationFailed
+}
+
+impl fmt::Display for ProtocolError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ProtocolError::Malformed => write!(f, "malformed protocol message"),
+            ProtocolError::UnexpectedMessage => write!(f, "unexpected protocol message"),
+            ProtocolError::AuthenticationFailed => write!(f, "authentication failed")
+        }
+    }
+}
+
+impl Error for ProtocolError { }
+
+
+#[cfg(test)]
+mod tests {
+    use core::*;
+    use quickcheck::quickcheck;
+    use rand;
+    use structured::StructuredDigester;
+    use super::{Prover, ProtocolError, Verifier, PROVER_CONTEXT, VERIFIER_CONTEXT};
+
+    const KEY: [u32; 4] = [0x833D3433, 0x009F389F, 0x2398E64F, 0x417ACF39];
+
+    fn pair<P: Permutation>(mutual: bool) -> (Verifier<P>, Prover<P>) {
+        (Verifier::new(KEY, b"verifier", b"prover", mutual),
+         Prover::new(KEY, b"verifier", b"prover", mutual))
+    }
+
+    fn run<P: Permutation>(verifier_id: Vec<u8>, prover_id: Vec<u8>, key: [u32; 4], mutual: bool) -> bool {
+        let mut rng = rand::thread_rng();
+        let mut verifier: Verifier<P> = Verifier::new(key, &verifier_id, &prover_id, mutual);
+        let mut prover: Prover<P> = Prover::new(key, &verifier_id, &prover_id, mutual);
+
+        let challenge = verifier.challenge(&mut rng).unwrap();
+        let response = prover.respond(&challenge, &mut rng).unwrap();
+        match verifier.verify(&response) {
+            Ok(Some(confirmation)) => {
+                mutual && prover.confirm(&confirmation).is_ok()
+                    && verifier.is_authenticated() && prover.is_authenticated()
+            }
+            Ok(None) => !mutual && verifier.is_authenticated() && !prover.is_authenticated(),
+            Err(_) => false
+        }
+    }
+
+    fn success<P: Permutation>() {
+        fn prop<P: Permutation>(verifier_id: Vec<u8>, prover_id: Vec<u8>, key: (u32, u32, u32, u32),
+                                mutual: bool) -> bool {
+            run::<P>(verifier_id, prover_id, [key.0, key.1, key.2, key.3], mutual)
+        }
+        quickcheck(prop::<P> as fn(Vec<u8>, Vec<u8>, (u32, u32, u32, u32), bool) -> bool);
+    }
+
+    #[test]
+    fn success_8() {
+        success::<Chaskey>();
+    }
+
+    #[test]
+    fn success_12() {
+        success::<Chaskey12>();
+    }
+
+    #[test]
+    fn success_16() {
+        success::<ChaskeyLTS>();
+    }
+
+    #[test]
+    fn layout() {
+        let mut rng = rand::thread_rng();
+        let (mut verifier, mut prover) = pair::<Chaskey>(true);
+        let challenge = verifier.challenge(&mut rng).unwrap();
+        assert_eq!(challenge.len(), 17);
+        assert_eq!(challenge[0], 1);
+        let response = prover.respond(&challenge, &mut rng).unwrap();
+        assert_eq!(response.len(), 33);
+        assert_eq!(response[0], 2);
+        let confirmation = verifier.verify(&response).unwrap().unwrap();
+        assert_eq!(confirmation.len(), 17);
+        assert_eq!(confirmation[0], 3);
+
+        let tag = |context: &[u8]| {
+            let mut mac: StructuredDigester<Chaskey> =
+                StructuredDigester::with_personalization(KEY, context);
+            mac.bytes(b"verifier");
+            mac.bytes(b"prover");
+            mac.bytes(&challenge[1..]);
+            mac.bytes(&response[1..17]);
+            mac.bool(true);
+            mac.finish().raw_bytes()
+        };
+        assert_eq!(response[17..], tag(PROVER_CONTEXT));
+        assert_eq!(confirmation[1..], tag(VERIFIER_CONTEXT));
+    }
+
+    #[test]
+    fn tampering() {
+        let mut rng = rand::thread_rng();
+        for i in 0..33 {
+            let (mut verifier, mut prover) = pair::<Chaskey>(true);
+            let challenge = verifier.challenge(&mut rng).unwrap();
+            let mut response = prover.respond(&challenge, &mut rng).unwrap();
+            response[i] ^= 1;
+            let expected = if i == 0 { ProtocolError::Malformed } else { ProtocolError::AuthenticationFailed };
+            assert_eq!(verifier.verify(&response), Err(expected));
+            assert!(!verifier.is_authenticated());
+        }
+        for i in 0..17 {
+            let (mut verifier, mut prover) = pair::<Chaskey>(true);
+            let challenge = verifier.challenge(&mut rng).unwrap();
+            let response = prover.respond(&challenge, &mut rng).unwrap();
+            let mut confirmation = verifier.verify(&response).unwrap().unwrap();
+            confirmation[i] ^= 1;
+            let expected = if i == 0 { ProtocolError::Malformed } else { ProtocolError::AuthenticationFailed };
+            assert_eq!(prover.confirm(&confirmation), Err(expected));
+            assert!(!prover.is_authenticated());
+        }
+    }
+
+    #[test]
+    fn replay() {
+        let mut rng = rand::thread_rng();
+        let (mut verifier, mut prover) = pair::<Chaskey>(false);
+        let challenge = verifier.challenge(&mut rng).unwrap();
+        let response = prover.respond(&challenge, &mut rng).unwrap();
+        assert_eq!(verifier.verify(&response), Ok(None));
+
+        // A recorded response doesn't answer a fresh challenge.
+        let (mut verifier, _) = pair::<Chaskey>(false);
+        verifier.challenge(&mut rng).unwrap();
+        assert_eq!(verifier.verify(&response), Err(ProtocolError::AuthenticationFailed));
+    }
+
+    #[test]
+    fn reflection() {
+        // The verifier's tag, relabelled as a response with the
+        // prover's nonce, doesn't pass for the prover's.
+        let mut rng = rand::thread_rng();
+        let (mut verifier, mut prover) = pair::<Chaskey>(true);
+        let challenge = verifier.challenge(&mut rng).unwrap();
+        let response = prover.respond(&challenge, &mut rng).unwrap();
+        let confirmation = verifier.verify(&response).unwrap().unwrap();
+
+        let (mut other, _) = pair::<Chaskey>(true);
+        let mut forged = vec![2];
+        forged.extend_from_slice(&response[1..17]);
+        forged.extend_from_slice(&confirmation[1..]);
+        other.challenge(&mut rng).unwrap();
+        assert!(other.verify(&forged).is_err());
+
+        // Nor does a prover accept its own response as confirmation.
+        let (mut verifier, mut prover) = pair::<Chaskey>(true);
+        let challenge = verifier.challenge(&mut rng).unwrap();
+        let response = prover.respond(&challenge, &mut rng).unwrap();
+        let mut forged = vec![3];
+        forged.extend_from_slice(&response[17..]);
+        assert_eq!(prover.confirm(&forged), Err(ProtocolError::AuthenticationFailed));
+    }
+
+    #[test]
+    fn mismatch() {
+        fn check(mut verifier: Verifier<Chaskey>, mut prover: Prover<Chaskey>) {
+            let mut rng = rand::thread_rng();
+            let challenge = verifier.challenge(&mut rng).unwrap();
+            let response = prover.respond(&challenge, &mut rng).unwrap();
+            assert_eq!(verifier.verify(&response), Err(ProtocolError::AuthenticationFailed));
+        }
+        check(Verifier::new(KEY, b"verifier", b"prover", true),
+              Prover::new(KEY, b"verifier", b"prover", false));
+        check(Verifier::new(KEY, b"verifier", b"prover", false),
+              Prover::new(KEY, b"verifier", b"other", false));
+        check(Verifier::new(KEY, b"verifier", b"prover", false),
+              Prover::new(KEY, b"other", b"prover", false));
+        // Ids are length-delimited, so moving bytes between them is
+        // a different session.
+        check(Verifier::new(KEY, b"verifier", b"prover", false),
+              Prover::new(KEY, b"verifie", b"rprover", false));
+        check(Verifier::new(KEY, b"verifier", b"prover", false),
+              Prover::new([1, 2, 3, 4], b"verifier", b"prover", false));
+    }
+
+    #[test]
+    fn order() {
+        let mut rng = rand::thread_rng();
+        let (mut verifier, mut prover) = pair::<Chaskey>(true);
+        assert_eq!(verifier.verify(&[2; 33]), Err(ProtocolError::UnexpectedMessage));
+        assert_eq!(verifier.challenge(&mut rng), Err(ProtocolError::UnexpectedMessage));
+        assert_eq!(prover.confirm(&[3; 17]), Err(ProtocolError::UnexpectedMessage));
+        assert!(prover.respond(&[1; 17], &mut rng).is_err());
+
+        let (mut verifier, mut prover) = pair::<Chaskey>(false);
+        let challenge = verifier.challenge(&mut rng).unwrap();
+        assert_eq!(verifier.challenge(&mut rng), Err(ProtocolError::UnexpectedMessage));
+        let response = prover.respond(&challenge, &mut rng).unwrap();
+        assert_eq!(prover.respond(&challenge, &mut rng), Err(ProtocolError::UnexpectedMessage));
+        // No confirmation in one-way mode.
+        assert_eq!(prover.confirm(&[3; 17]), Err(ProtocolError::UnexpectedMessage));
+        assert_eq!(verifier.verify(&response), Err(ProtocolError::UnexpectedMessage));
+
+        // Malformed messages fail the run.
+        let (mut verifier, mut prover) = pair::<Chaskey>(false);
+        assert_eq!(prover.respond(&[], &mut rng), Err(ProtocolError::Malformed));
+        let challenge = verifier.challenge(&mut rng).unwrap();
+        assert_eq!(verifier.verify(&challenge), Err(ProtocolError::Malformed));
+        let response = Prover::<Chaskey>::new(KEY, b"verifier", b"prover", false)
+            .respond(&challenge, &mut rng).unwrap();
+        assert_eq!(verifier.verify(&response), Err(ProtocolError::UnexpectedMessage));
+    }
+}
@@ -12,10 +12,11 @@
 //! ## References
 //!
 //! * [Notes at the CryptoLux
-//! website](https://www.cryptolux.org/index.php/Lightweight_Block_Ciphers#Chaskey_Cipher).
+//!   website](https://www.cryptolux.org/index.php/Lightweight_Block_Ciphers#Chaskey_Cipher).
 
 pub use core::*;
-use util::xor_u32x4;
+use util::{write_u32x4, xor_u32x4};
+use zeroize::Zeroize;
 
 
 /// Encryption function for the Chaskey block cipher, parametrized by
@@ -37,11 +38,41 @@ pub fn decrypt<P: Permutation>(msg: &mut [u32; 4], key: &[u32; 4]) {
 }
 
 
+/// Encrypt or decrypt `data` in place with the Chaskey block cipher
+/// in counter (CTR) mode, parametrized by the permutation to use.
+/// The keystream is the encryption of `nonce`, `nonce + 1`, `nonce +
+/// 2`, etc., with the nonce read as a 128-bit little-endian number.
+///
+/// CTR mode provides no authentication, so its output must be
+/// protected with a MAC; and **a nonce must never be used twice with
+/// the same key**.  The keystream is wiped as it is used.
+pub fn ctr<P: Permutation>(key: &[u32; 4], nonce: &[u32; 4], data: &mut [u8]) {
+    let start = (nonce[0] as u128)
+        | (nonce[1] as u128) << 32
+        | (nonce[2] as u128) << 64
+        | (nonce[3] as u128) << 96;
+    for (i, chunk) in data.chunks_mut(16).enumerate() {
+        let counter = start.wrapping_add(i as u128);
+        let mut block = [counter as u32,
+                         (counter >> 32) as u32,
+                         (counter >> 64) as u32,
+                         (counter >> 96) as u32];
+        encrypt::<P>(&mut block, key);
+        let mut keystream = write_u32x4(&block);
+        for (byte, k) in chunk.iter_mut().zip(keystream.iter()) {
+            *byte ^= *k;
+        }
+        block.zeroize();
+        keystream.zeroize();
+    }
+}
+
+
 #[cfg(test)]
 mod tests {    
     use byteorder::{ByteOrder, LittleEndian};
     use core::*;
-    use super::{encrypt, decrypt};
+    use super::{ctr, encrypt, decrypt};
     use quickcheck::{Arbitrary, Gen, quickcheck};
 
     #[derive(PartialEq, Eq, Clone, Copy, Debug)]
@@ -115,6 +146,58 @@ mod tests {
         assert_eq!(&buf, &plaintext);
     }
 
+    fn ctr_involution<P: Permutation>() {
+        fn prop<P: Permutation>(msg: Vec<u8>, key: Block, nonce: Block) -> bool {
+            let mut buf = msg.clone();
+            ctr::<P>(&key.0, &nonce.0, &mut buf);
+            ctr::<P>(&key.0, &nonce.0, &mut buf);
+            buf == msg
+        }
+        quickcheck(prop::<P> as fn(Vec<u8>, Block, Block) -> bool);
+    }
+
+    #[test]
+    fn ctr8_ctr8() {
+        ctr_involution::<Chaskey>();
+    }
+
+    #[test]
+    fn ctr12_ctr12() {
+        ctr_involution::<Chaskey12>();
+    }
+
+    #[test]
+    fn ctr16_ctr16() {
+        ctr_involution::<ChaskeyLTS>();
+    }
+
+    /// The keystream is the encryption of successive counter values,
+    /// which carry over from one word to the next.
+    #[test]
+    fn ctr_keystream() {
+        let key = [0x833D3433, 0x009F389F, 0x2398E64F, 0x417ACF39];
+        let nonce = [0xFFFFFFFE, 0xFFFFFFFF, 0x00000000, 0x00000001];
+        let mut stream = [0u8; 40];
+        ctr::<Chaskey>(&key, &nonce, &mut stream);
+
+        let mut counters = [nonce,
+                            [0xFFFFFFFF, 0xFFFFFFFF, 0x00000000, 0x00000001],
+                            [0x00000000, 0x00000000, 0x00000001, 0x00000001]];
+        for (i, counter) in counters.iter_mut().enumerate() {
+            encrypt::<Chaskey>(counter, &key);
+            let expected = &to_bytes(counter)[..stream[16 * i..].len().min(16)];
+            assert_eq!(&stream[16 * i..16 * i + expected.len()], expected);
+        }
+    }
+
+    fn to_bytes(words: &[u32; 4]) -> [u8; 16] {
+        let mut bytes = [0u8; 16];
+        for (chunk, word) in bytes.chunks_mut(4).zip(words.iter()) {
+            LittleEndian::write_u32(chunk, *word);
+        }
+        bytes
+    }
+
     fn to_u32x4(bytes: &[u8; 16]) -> [u32; 4] {
         [LittleEndian::read_u32(&bytes[0..4]),
          LittleEndian::read_u32(&bytes[4..8]),
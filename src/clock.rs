@@ -0,0 +1,87 @@
+//! Sources of the current time, for code that deals with expiry
+//! times.
+//!
+//! Times are whole seconds since the Unix epoch.  Code that needs the
+//! time takes a `Clock`, so that tests can use a `ManualClock` instead
+//! of waiting for real time to pass.
+
+use std::cell::Cell;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+
+/// A source of the current time.
+pub trait Clock {
+    /// The current time, in seconds since the Unix epoch.
+    fn now(&self) -> u64;
+}
+
+impl<C: Clock + ?Sized> Clock for &C {
+    fn now(&self) -> u64 {
+        (**self).now()
+    }
+}
+
+/// The system's wall clock.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    /// Times before the epoch are reported as 0.
+    fn now(&self) -> u64 {
+        SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+    }
+}
+
+/// A clock that only moves when told to.
+///
+/// ```
+/// use chaskey::clock::{Clock, ManualClock};
+///
+/// let clock = ManualClock::new(1000);
+/// clock.advance(60);
+/// assert_eq!(clock.now(), 1060);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ManualClock(Cell<u64>);
+
+impl ManualClock {
+    /// A clock that reads `now` until changed.
+    pub fn new(now: u64) -> ManualClock {
+        ManualClock(Cell::new(now))
+    }
+
+    /// Set the time.
+    pub fn set(&self, now: u64) {
+        self.0.set(now);
+    }
+
+    /// Move the time forward by `secs` seconds.
+    pub fn advance(&self, secs: u64) {
+        self.0.set(self.0.get() + secs);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> u64 {
+        self.0.get()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::{Clock, ManualClock, SystemClock};
+
+    #[test]
+    fn clocks() {
+        // 2020-01-01.
+        assert!(SystemClock.now() > 1577836800);
+
+        let clock = ManualClock::new(5);
+        let by_ref: &dyn Clock = &&clock;
+        clock.advance(10);
+        assert_eq!(by_ref.now(), 15);
+        clock.set(3);
+        assert_eq!(clock.now(), 3);
+    }
+}
@@ -3,6 +3,10 @@
 //! At first glance some of these may appear to be internal, but they
 //! are documented in the Chaskey papers, so they're worth exporting.
 
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
 /// Function used in the Chaskey key schedule.
 #[inline(always)]
 pub fn times_two(key: &[u32; 4]) -> [u32; 4] {
@@ -41,6 +45,30 @@ pub fn unround(v: &mut [u32; 4]) {
     v[0]  = v[0].wrapping_sub(v[1]); v[2]  = v[2].wrapping_sub(v[3]);
 }
 
+/// The Chaskey round function, applied to several independent states
+/// at once.  Each step of the round is performed on every state
+/// before moving on to the next one, so that the processor can
+/// overlap the otherwise strictly sequential add-rotate-xor chains.
+#[inline(always)]
+pub fn round_lanes<const N: usize>(v: &mut [[u32; 4]; N]) {
+    for v in v.iter_mut() {
+        v[0]  = v[0].wrapping_add(v[1]); v[2]  = v[2].wrapping_add(v[3]);
+        v[1]  = v[1].rotate_left(5);     v[3]  = v[3].rotate_left(8);
+    }
+    for v in v.iter_mut() {
+        v[1] ^= v[0];                    v[3] ^= v[2];
+        v[0]  = v[0].rotate_left(16);
+    }
+    for v in v.iter_mut() {
+        v[2]  = v[2].wrapping_add(v[1]); v[0]  = v[0].wrapping_add(v[3]);
+        v[1]  = v[1].rotate_left(7);     v[3]  = v[3].rotate_left(13);
+    }
+    for v in v.iter_mut() {
+        v[1] ^= v[2];                    v[3] ^= v[0];
+        v[2]  = v[2].rotate_left(16);
+    }
+}
+
 /// Chaskey processors in this library are parametrized by
 /// implementations of this trait in order to select the variant of
 /// Chaskey to use.
@@ -50,6 +78,16 @@ pub fn unround(v: &mut [u32; 4]) {
 pub trait Permutation {
     fn permute(state: &mut [u32; 4]);
     fn invert(state: &mut [u32; 4]);
+
+    /// Apply the permutation to `N` independent states.  The default
+    /// permutes them one after the other; the built-in permutations
+    /// override it to interleave their rounds.
+    #[inline(always)]
+    fn permute_lanes<const N: usize>(states: &mut [[u32; 4]; N]) {
+        for state in states.iter_mut() {
+            Self::permute(state);
+        }
+    }
 }
 
 /// The original Chaskey permutation (8 rounds).
@@ -74,6 +112,14 @@ impl Permutation for Chaskey {
         unround(state); unround(state); 
         unround(state); unround(state);
     }
+
+    #[inline(always)]
+    fn permute_lanes<const N: usize>(states: &mut [[u32; 4]; N]) {
+        round_lanes(states); round_lanes(states);
+        round_lanes(states); round_lanes(states);
+        round_lanes(states); round_lanes(states);
+        round_lanes(states); round_lanes(states);
+    }
 }
 
 
@@ -105,6 +151,17 @@ impl Permutation for Chaskey12 {
         unround(state); unround(state); 
         unround(state); unround(state);
     }
+
+    #[inline(always)]
+    fn permute_lanes<const N: usize>(states: &mut [[u32; 4]; N]) {
+        round_lanes(states); round_lanes(states);
+        round_lanes(states); round_lanes(states);
+        round_lanes(states); round_lanes(states);
+
+        round_lanes(states); round_lanes(states);
+        round_lanes(states); round_lanes(states);
+        round_lanes(states); round_lanes(states);
+    }
 }
 
 
@@ -140,8 +197,80 @@ impl Permutation for ChaskeyLTS {
         unround(state); unround(state); 
         unround(state); unround(state);
     }
+
+    #[inline(always)]
+    fn permute_lanes<const N: usize>(states: &mut [[u32; 4]; N]) {
+        round_lanes(states); round_lanes(states);
+        round_lanes(states); round_lanes(states);
+        round_lanes(states); round_lanes(states);
+        round_lanes(states); round_lanes(states);
+
+        round_lanes(states); round_lanes(states);
+        round_lanes(states); round_lanes(states);
+        round_lanes(states); round_lanes(states);
+        round_lanes(states); round_lanes(states);
+    }
+}
+
+
+
+/// A Chaskey variant selected at run time, for code such as key files
+/// and command-line tools that can't pick a `Permutation` type
+/// statically.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Variant {
+    /// The original 8-round Chaskey (`Chaskey`).
+    Chaskey,
+    /// The 12-round Chaskey-12 (`Chaskey12`).
+    Chaskey12,
+    /// The 16-round Chaskey-LTS (`ChaskeyLTS`).
+    ChaskeyLTS
+}
+
+impl Variant {
+    /// The variant's canonical name: `chaskey-8`, `chaskey-12` or
+    /// `chaskey-lts`.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Variant::Chaskey => "chaskey-8",
+            Variant::Chaskey12 => "chaskey-12",
+            Variant::ChaskeyLTS => "chaskey-lts"
+        }
+    }
 }
 
+impl fmt::Display for Variant {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Parses the canonical names, as well as the short forms `8`, `12`,
+/// `lts` and `16`, ignoring case.
+impl FromStr for Variant {
+    type Err = ParseVariantError;
+
+    fn from_str(s: &str) -> Result<Variant, ParseVariantError> {
+        match &*s.to_ascii_lowercase() {
+            "chaskey-8" | "8" => Ok(Variant::Chaskey),
+            "chaskey-12" | "12" => Ok(Variant::Chaskey12),
+            "chaskey-lts" | "lts" | "16" => Ok(Variant::ChaskeyLTS),
+            _ => Err(ParseVariantError(()))
+        }
+    }
+}
+
+/// The error returned when parsing an unknown `Variant` name.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseVariantError(());
+
+impl fmt::Display for ParseVariantError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("unknown Chaskey variant (expected chaskey-8, chaskey-12 or chaskey-lts)")
+    }
+}
+
+impl Error for ParseVariantError {}
 
 #[cfg(test)]
 mod tests {    
@@ -193,4 +322,61 @@ mod tests {
         quickcheck(prop::<P> as fn(Block) -> bool);
     }
 
+    #[test]
+    fn permute8_lanes() {
+        permute_lanes::<Chaskey>()
+    }
+
+    #[test]
+    fn permute12_lanes() {
+        permute_lanes::<Chaskey12>()
+    }
+
+    #[test]
+    fn permute16_lanes() {
+        permute_lanes::<ChaskeyLTS>()
+    }
+
+    fn permute_lanes<P: Permutation>() {
+        fn prop<P: Permutation>(a: Block, b: Block, c: Block) -> bool {
+            let mut lanes = [a.0, b.0, c.0];
+            P::permute_lanes(&mut lanes);
+            let mut expected = [a.0, b.0, c.0];
+            for state in expected.iter_mut() {
+                P::permute(state);
+            }
+            lanes == expected
+        }
+        quickcheck(prop::<P> as fn(Block, Block, Block) -> bool);
+    }
+
+    #[test]
+    fn permute_lanes_default() {
+        // A permutation that doesn't override `permute_lanes`.
+        enum Plain {}
+        impl Permutation for Plain {
+            fn permute(state: &mut [u32; 4]) { Chaskey::permute(state) }
+            fn invert(state: &mut [u32; 4]) { Chaskey::invert(state) }
+        }
+
+        fn prop(a: Block, b: Block) -> bool {
+            let mut lanes = [a.0, b.0];
+            Plain::permute_lanes(&mut lanes);
+            let mut expected = [a.0, b.0];
+            Chaskey::permute_lanes(&mut expected);
+            lanes == expected
+        }
+        quickcheck(prop as fn(Block, Block) -> bool);
+    }
+
+    #[test]
+    fn variant_names() {
+        for &v in [Variant::Chaskey, Variant::Chaskey12, Variant::ChaskeyLTS].iter() {
+            assert_eq!(v.to_string().parse(), Ok(v));
+        }
+        assert_eq!("LTS".parse(), Ok(Variant::ChaskeyLTS));
+        assert_eq!("12".parse(), Ok(Variant::Chaskey12));
+        assert!("chaskey-9".parse::<Variant>().is_err());
+    }
+
 }
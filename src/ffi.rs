@@ -0,0 +1,349 @@
+//! A C interface to the MAC and the block cipher, for the `ffi`
+//! feature.  The C declarations are in `include/chaskey.h`, which also
+//! documents the functions; this module follows it.
+//!
+//! The `chaskey-ffi` crate in `ffi/` builds this module as C
+//! libraries, `libchaskey_ffi.so` and `libchaskey_ffi.a`, for C
+//! programs to link with.
+
+use cipher;
+use core::{Chaskey, Chaskey12, ChaskeyLTS, Permutation, Variant};
+use std::os::raw::c_int;
+use std::slice;
+use subtle::ConstantTimeEq;
+use util::{read_u32x4, write_u32x4};
+use zeroize::Zeroize;
+use Digester;
+
+
+pub const CHASKEY_OK: c_int = 0;
+pub const CHASKEY_ERR_NULL: c_int = -1;
+pub const CHASKEY_ERR_VARIANT: c_int = -2;
+pub const CHASKEY_ERR_TAG_LENGTH: c_int = -3;
+pub const CHASKEY_ERR_VERIFY: c_int = -4;
+
+
+/// The `chaskey_ctx` of the C interface: a `Digester` of any variant.
+pub enum ChaskeyCtx {
+    Chaskey(Digester<Chaskey>),
+    Chaskey12(Digester<Chaskey12>),
+    ChaskeyLTS(Digester<ChaskeyLTS>)
+}
+
+impl ChaskeyCtx {
+    fn write(&mut self, data: &[u8]) {
+        match *self {
+            ChaskeyCtx::Chaskey(ref mut d) => d.write(data),
+            ChaskeyCtx::Chaskey12(ref mut d) => d.write(data),
+            ChaskeyCtx::ChaskeyLTS(ref mut d) => d.write(data)
+        }
+    }
+
+    fn reset(&mut self) {
+        match *self {
+            ChaskeyCtx::Chaskey(ref mut d) => d.reset(),
+            ChaskeyCtx::Chaskey12(ref mut d) => d.reset(),
+            ChaskeyCtx::ChaskeyLTS(ref mut d) => d.reset()
+        }
+    }
+
+    fn tag(&self) -> [u8; 16] {
+        match *self {
+            ChaskeyCtx::Chaskey(ref d) => d.finish().raw_bytes(),
+            ChaskeyCtx::Chaskey12(ref d) => d.finish().raw_bytes(),
+            ChaskeyCtx::ChaskeyLTS(ref d) => d.finish().raw_bytes()
+        }
+    }
+}
+
+/// The variant with the given round count.
+fn variant(rounds: c_int) -> Option<Variant> {
+    match rounds {
+        8 => Some(Variant::Chaskey),
+        12 => Some(Variant::Chaskey12),
+        16 => Some(Variant::ChaskeyLTS),
+        _ => None
+    }
+}
+
+/// Read a 16-byte key or block.
+unsafe fn read_block(ptr: *const u8) -> [u32; 4] {
+    read_u32x4(slice::from_raw_parts(ptr, 16))
+}
+
+/// A byte slice from a pointer that may be NULL if `len` is zero.
+unsafe fn data<'a>(ptr: *const u8, len: usize) -> Option<&'a [u8]> {
+    if len == 0 {
+        Some(&[])
+    } else if ptr.is_null() {
+        None
+    } else {
+        Some(slice::from_raw_parts(ptr, len))
+    }
+}
+
+
+/// # Safety
+///
+/// `ctx` must be valid for writes, and `key` for reading 16 bytes.
+#[no_mangle]
+pub unsafe extern "C" fn chaskey_new(ctx: *mut *mut ChaskeyCtx, variant_rounds: c_int,
+                                     key: *const u8) -> c_int {
+    if ctx.is_null() || key.is_null() {
+        return CHASKEY_ERR_NULL;
+    }
+    let mut k = read_block(key);
+    let new = match variant(variant_rounds) {
+        Some(Variant::Chaskey) => ChaskeyCtx::Chaskey(Digester::new(k)),
+        Some(Variant::Chaskey12) => ChaskeyCtx::Chaskey12(Digester::new(k)),
+        Some(Variant::ChaskeyLTS) => ChaskeyCtx::ChaskeyLTS(Digester::new(k)),
+        None => {
+            k.zeroize();
+            return CHASKEY_ERR_VARIANT;
+        }
+    };
+    k.zeroize();
+    *ctx = Box::into_raw(Box::new(new));
+    CHASKEY_OK
+}
+
+/// # Safety
+///
+/// `ctx` must be NULL or a context from `chaskey_new` that has not
+/// been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn chaskey_free(ctx: *mut ChaskeyCtx) {
+    if !ctx.is_null() {
+        drop(Box::from_raw(ctx));
+    }
+}
+
+/// # Safety
+///
+/// `ctx` must be NULL or a live context from `chaskey_new`.
+#[no_mangle]
+pub unsafe extern "C" fn chaskey_reset(ctx: *mut ChaskeyCtx) -> c_int {
+    match ctx.as_mut() {
+        Some(ctx) => {
+            ctx.reset();
+            CHASKEY_OK
+        }
+        None => CHASKEY_ERR_NULL
+    }
+}
+
+/// # Safety
+///
+/// `ctx` must be NULL or a live context from `chaskey_new`, and
+/// `data` must be valid for reading `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn chaskey_update(ctx: *mut ChaskeyCtx, data_ptr: *const u8,
+                                        len: usize) -> c_int {
+    match (ctx.as_mut(), data(data_ptr, len)) {
+        (Some(ctx), Some(bytes)) => {
+            ctx.write(bytes);
+            CHASKEY_OK
+        }
+        _ => CHASKEY_ERR_NULL
+    }
+}
+
+/// # Safety
+///
+/// `ctx` must be NULL or a live context from `chaskey_new`, and `tag`
+/// must be valid for writing `tag_len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn chaskey_final(ctx: *const ChaskeyCtx, tag: *mut u8,
+                                       tag_len: usize) -> c_int {
+    let ctx = match ctx.as_ref() {
+        Some(ctx) if !tag.is_null() => ctx,
+        _ => return CHASKEY_ERR_NULL
+    };
+    if tag_len == 0 || tag_len > 16 {
+        return CHASKEY_ERR_TAG_LENGTH;
+    }
+    let full = ctx.tag();
+    slice::from_raw_parts_mut(tag, tag_len).copy_from_slice(&full[..tag_len]);
+    CHASKEY_OK
+}
+
+/// # Safety
+///
+/// `ctx` must be NULL or a live context from `chaskey_new`, and `tag`
+/// must be valid for reading `tag_len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn chaskey_verify(ctx: *const ChaskeyCtx, tag: *const u8,
+                                        tag_len: usize) -> c_int {
+    let ctx = match ctx.as_ref() {
+        Some(ctx) if !tag.is_null() => ctx,
+        _ => return CHASKEY_ERR_NULL
+    };
+    if tag_len == 0 || tag_len > 16 {
+        return CHASKEY_ERR_TAG_LENGTH;
+    }
+    let expected = slice::from_raw_parts(tag, tag_len);
+    if bool::from(ctx.tag()[..tag_len].ct_eq(expected)) {
+        CHASKEY_OK
+    } else {
+        CHASKEY_ERR_VERIFY
+    }
+}
+
+/// # Safety
+///
+/// `key` must be valid for reading 16 bytes, `data` for reading `len`
+/// bytes and `tag` for writing `tag_len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn chaskey_mac(variant_rounds: c_int, key: *const u8,
+                                     data_ptr: *const u8, len: usize,
+                                     tag: *mut u8, tag_len: usize) -> c_int {
+    let mut ctx = std::ptr::null_mut();
+    let status = chaskey_new(&mut ctx, variant_rounds, key);
+    if status != CHASKEY_OK {
+        return status;
+    }
+    let mut status = chaskey_update(ctx, data_ptr, len);
+    if status == CHASKEY_OK {
+        status = chaskey_final(ctx, tag, tag_len);
+    }
+    chaskey_free(ctx);
+    status
+}
+
+unsafe fn crypt_block(variant_rounds: c_int, key: *const u8, block: *mut u8,
+                      crypt: fn(Variant, &mut [u32; 4], &[u32; 4])) -> c_int {
+    if key.is_null() || block.is_null() {
+        return CHASKEY_ERR_NULL;
+    }
+    let variant = match variant(variant_rounds) {
+        Some(variant) => variant,
+        None => return CHASKEY_ERR_VARIANT
+    };
+    let mut k = read_block(key);
+    let mut b = read_block(block);
+    crypt(variant, &mut b, &k);
+    slice::from_raw_parts_mut(block, 16).copy_from_slice(&write_u32x4(&b));
+    k.zeroize();
+    b.zeroize();
+    CHASKEY_OK
+}
+
+fn encrypt(variant: Variant, block: &mut [u32; 4], key: &[u32; 4]) {
+    fn go<P: Permutation>(block: &mut [u32; 4], key: &[u32; 4]) {
+        cipher::encrypt::<P>(block, key)
+    }
+    match variant {
+        Variant::Chaskey => go::<Chaskey>(block, key),
+        Variant::Chaskey12 => go::<Chaskey12>(block, key),
+        Variant::ChaskeyLTS => go::<ChaskeyLTS>(block, key)
+    }
+}
+
+fn decrypt(variant: Variant, block: &mut [u32; 4], key: &[u32; 4]) {
+    fn go<P: Permutation>(block: &mut [u32; 4], key: &[u32; 4]) {
+        cipher::decrypt::<P>(block, key)
+    }
+    match variant {
+        Variant::Chaskey => go::<Chaskey>(block, key),
+        Variant::Chaskey12 => go::<Chaskey12>(block, key),
+        Variant::ChaskeyLTS => go::<ChaskeyLTS>(block, key)
+    }
+}
+
+/// # Safety
+///
+/// `key` must be valid for reading 16 bytes, and `block` for reading
+/// and writing 16 bytes.
+#[no_mangle]
+pub unsafe extern "C" fn chaskey_encrypt_block(variant_rounds: c_int, key: *const u8,
+                                               block: *mut u8) -> c_int {
+    crypt_block(variant_rounds, key, block, encrypt)
+}
+
+/// # Safety
+///
+/// `key` must be valid for reading 16 bytes, and `block` for reading
+/// and writing 16 bytes.
+#[no_mangle]
+pub unsafe extern "C" fn chaskey_decrypt_block(variant_rounds: c_int, key: *const u8,
+                                               block: *mut u8) -> c_int {
+    crypt_block(variant_rounds, key, block, decrypt)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::ptr;
+    use super::*;
+
+    const KEY: [u8; 16] = [0x33, 0x34, 0x3d, 0x83, 0x9f, 0x38, 0x9f, 0x00,
+                           0x4f, 0xe6, 0x98, 0x23, 0x39, 0xcf, 0x7a, 0x41];
+
+    /// The 8-round tag of `00 01 02` under `KEY`.
+    const TAG: [u8; 16] = [0x64, 0xb2, 0xdb, 0x1b, 0xd8, 0x80, 0x76, 0xa0,
+                           0xb8, 0x2a, 0x5b, 0x8e, 0x13, 0x04, 0x66, 0x20];
+
+    #[test]
+    fn incremental_and_one_shot() {
+        unsafe {
+            let mut ctx = ptr::null_mut();
+            assert_eq!(chaskey_new(&mut ctx, 8, KEY.as_ptr()), CHASKEY_OK);
+            assert_eq!(chaskey_update(ctx, [0u8, 1].as_ptr(), 2), CHASKEY_OK);
+            assert_eq!(chaskey_update(ctx, ptr::null(), 0), CHASKEY_OK);
+            assert_eq!(chaskey_update(ctx, [2u8].as_ptr(), 1), CHASKEY_OK);
+
+            let mut tag = [0u8; 16];
+            assert_eq!(chaskey_final(ctx, tag.as_mut_ptr(), 16), CHASKEY_OK);
+            assert_eq!(tag, TAG);
+            assert_eq!(chaskey_verify(ctx, TAG.as_ptr(), 16), CHASKEY_OK);
+            assert_eq!(chaskey_verify(ctx, TAG.as_ptr(), 4), CHASKEY_OK);
+            assert_eq!(chaskey_verify(ctx, TAG[1..].as_ptr(), 4), CHASKEY_ERR_VERIFY);
+
+            assert_eq!(chaskey_reset(ctx), CHASKEY_OK);
+            assert_eq!(chaskey_verify(ctx, TAG.as_ptr(), 16), CHASKEY_ERR_VERIFY);
+            chaskey_free(ctx);
+
+            let mut short = [0u8; 8];
+            assert_eq!(chaskey_mac(8, KEY.as_ptr(), [0u8, 1, 2].as_ptr(), 3,
+                                   short.as_mut_ptr(), 8), CHASKEY_OK);
+            assert_eq!(short, TAG[..8]);
+        }
+    }
+
+    #[test]
+    fn errors() {
+        unsafe {
+            let mut ctx = ptr::null_mut();
+            let mut tag = [0u8; 16];
+            assert_eq!(chaskey_new(&mut ctx, 10, KEY.as_ptr()), CHASKEY_ERR_VARIANT);
+            assert_eq!(chaskey_new(&mut ctx, 8, ptr::null()), CHASKEY_ERR_NULL);
+            assert_eq!(chaskey_new(ptr::null_mut(), 8, KEY.as_ptr()), CHASKEY_ERR_NULL);
+            assert!(ctx.is_null());
+            assert_eq!(chaskey_update(ptr::null_mut(), KEY.as_ptr(), 1), CHASKEY_ERR_NULL);
+            assert_eq!(chaskey_mac(8, KEY.as_ptr(), ptr::null(), 1, tag.as_mut_ptr(), 16),
+                       CHASKEY_ERR_NULL);
+            assert_eq!(chaskey_mac(8, KEY.as_ptr(), ptr::null(), 0, tag.as_mut_ptr(), 0),
+                       CHASKEY_ERR_TAG_LENGTH);
+            assert_eq!(chaskey_mac(8, KEY.as_ptr(), ptr::null(), 0, tag.as_mut_ptr(), 17),
+                       CHASKEY_ERR_TAG_LENGTH);
+            assert_eq!(chaskey_encrypt_block(12, KEY.as_ptr(), ptr::null_mut()), CHASKEY_ERR_NULL);
+            assert_eq!(chaskey_decrypt_block(0, KEY.as_ptr(), tag.as_mut_ptr()), CHASKEY_ERR_VARIANT);
+            chaskey_free(ptr::null_mut());
+        }
+    }
+
+    #[test]
+    fn block_cipher() {
+        // The FELICS Chaskey-LTS test vector, as bytes.
+        let key = write_u32x4(&[0x68e90956, 0x29e3585f, 0x98ecec40, 0x2f9822c5]);
+        let plaintext = write_u32x4(&[0x262823b8, 0x5e405efd, 0xa901a369, 0xd87aea78]);
+        let ciphertext = write_u32x4(&[0x4d8d60d5, 0x7b34bfa2, 0x2f77f8ab, 0x07deeddf]);
+        let mut block = plaintext;
+        unsafe {
+            assert_eq!(chaskey_encrypt_block(16, key.as_ptr(), block.as_mut_ptr()), CHASKEY_OK);
+            assert_eq!(block, ciphertext);
+            assert_eq!(chaskey_decrypt_block(16, key.as_ptr(), block.as_mut_ptr()), CHASKEY_OK);
+        }
+        assert_eq!(block, plaintext);
+    }
+}
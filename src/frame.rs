@@ -0,0 +1,377 @@
+//! Authenticated frames with replay protection, for packet links such
+//! as sensor radios.
+//!
+//! A frame is a 7-byte header, the payload, and a truncated Chaskey
+//! tag:
+//!
+//! ```text
+//! frame  = key id || sequence number || length || payload || tag
+//! tag    = first tag_len bytes of Chaskey(key, header || payload)
+//! ```
+//!
+//! The key id is 1 byte, the sequence number 4 bytes and the payload
+//! length 2 bytes, the last two big-endian.  Frames are not
+//! encrypted.
+//!
+//! The sender picks the sequence numbers, which must never repeat
+//! under one key.  The receiver keeps a sliding window over the last
+//! 64 sequence numbers, in the style of IPsec: it accepts each
+//! sequence number at most once, in any order, as long as it is no
+//! more than 63 behind the highest one accepted so far.  So lost and
+//! reordered frames are fine, while duplicated and very late ones are
+//! rejected.
+//!
+//! ```
+//! # extern crate chaskey;
+//! use chaskey::Chaskey;
+//! use chaskey::frame::{Opener, Sealer};
+//!
+//! # fn main() {
+//! let key = [1, 2, 3, 4];
+//! let mut sealer: Sealer<Chaskey> = Sealer::new(7, key, 8);
+//! let mut opener: Opener<Chaskey> = Opener::new(7, key, 8);
+//!
+//! let first = sealer.seal(1, b"21.5C").unwrap();
+//! let second = sealer.seal(2, b"21.7C").unwrap();
+//! assert_eq!(opener.open(&second), Ok(&b"21.7C"[..]));
+//! assert_eq!(opener.open(&first), Ok(&b"21.5C"[..]));
+//! // Duplicates are rejected.
+//! assert!(opener.open(&first).is_err());
+//! # }
+//! ```
+
+use core::Permutation;
+use std::error::Error;
+use std::fmt;
+use {Digester, TruncatedTag};
+
+/// The length of a frame header, in bytes.
+pub const HEADER_LEN: usize = 7;
+
+/// The number of sequence numbers a `ReplayWindow` keeps track of.
+pub const WINDOW_SIZE: u32 = 64;
+
+
+/// Seals frames under one key.
+pub struct Sealer<P> {
+    key_id: u8,
+    digester: Digester<P>,
+    tag_len: usize
+}
+
+impl<P: Permutation> Sealer<P> {
+    /// Make a sealer that puts `key_id` in its frames and tags them
+    /// with `tag_len` bytes.
+    ///
+    /// # Panics
+    ///
+    /// Unless `tag_len` is between 4 and 16.
+    pub fn new(key_id: u8, key: [u32; 4], tag_len: usize) -> Sealer<P> {
+        check_tag_len(tag_len);
+        Sealer { key_id, digester: Digester::new(key), tag_len }
+    }
+
+    /// Seal `payload` in a frame with sequence number `seq`.  Fails
+    /// only if the payload is longer than 65535 bytes.
+    pub fn seal(&mut self, seq: u32, payload: &[u8]) -> Result<Vec<u8>, FrameError> {
+        if payload.len() > u16::MAX as usize {
+            return Err(FrameError::PayloadTooLong);
+        }
+        let mut frame = Vec::with_capacity(HEADER_LEN + payload.len() + self.tag_len);
+        frame.push(self.key_id);
+        frame.extend_from_slice(&seq.to_be_bytes());
+        frame.extend_from_slice(&(payload.len() as u16).to_be_bytes());
+        frame.extend_from_slice(payload);
+        let tag = tag(&mut self.digester, &frame, self.tag_len);
+        frame.extend_from_slice(tag.as_bytes());
+        Ok(frame)
+    }
+}
+
+
+/// Opens frames under one key, rejecting replays.
+pub struct Opener<P> {
+    key_id: u8,
+    digester: Digester<P>,
+    tag_len: usize,
+    window: ReplayWindow
+}
+
+impl<P: Permutation> Opener<P> {
+    /// Make an opener for frames with key id `key_id` and `tag_len`
+    /// bytes of tag, with an empty replay window.
+    ///
+    /// # Panics
+    ///
+    /// Unless `tag_len` is between 4 and 16.
+    pub fn new(key_id: u8, key: [u32; 4], tag_len: usize) -> Opener<P> {
+        check_tag_len(tag_len);
+        Opener { key_id, digester: Digester::new(key), tag_len, window: ReplayWindow::new() }
+    }
+
+    /// The replay window.
+    pub fn window(&self) -> &ReplayWindow {
+        &self.window
+    }
+
+    /// Open a frame, and return its payload.  The frame's sequence
+    /// number is only marked as seen if the frame is authentic.
+    pub fn open<'a>(&mut self, frame: &'a [u8]) -> Result<&'a [u8], FrameError> {
+        if frame.len() < HEADER_LEN + self.tag_len {
+            return Err(FrameError::TooShort);
+        }
+        if frame[0] != self.key_id {
+            return Err(FrameError::UnknownKey(frame[0]));
+        }
+        let seq = u32::from_be_bytes([frame[1], frame[2], frame[3], frame[4]]);
+        let len = u16::from_be_bytes([frame[5], frame[6]]) as usize;
+        if frame.len() != HEADER_LEN + len + self.tag_len {
+            return Err(FrameError::Length);
+        }
+        self.window.check(seq)?;
+
+        let (authenticated, received) = frame.split_at(HEADER_LEN + len);
+        let expected = tag(&mut self.digester, authenticated, self.tag_len);
+        if TruncatedTag::new(received) != Some(expected) {
+            return Err(FrameError::Authentication);
+        }
+        self.window.accept(seq)?;
+        Ok(&authenticated[HEADER_LEN..])
+    }
+}
+
+fn check_tag_len(tag_len: usize) {
+    assert!((4..=16).contains(&tag_len), "invalid frame tag length {}", tag_len);
+}
+
+fn tag<P: Permutation>(digester: &mut Digester<P>, msg: &[u8], tag_len: usize) -> TruncatedTag {
+    digester.reset();
+    digester.write(msg);
+    digester.finish().truncate(tag_len)
+}
+
+
+/// A sliding window of the sequence numbers seen lately.
+///
+/// It remembers the highest sequence number seen, and which of the
+/// `WINDOW_SIZE - 1` before it have been seen.  Anything older counts
+/// as seen.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReplayWindow {
+    highest: u32,
+    // Bit `i` is set when `highest - i` has been seen.
+    seen: u64
+}
+
+impl ReplayWindow {
+    /// An empty window, in which any sequence number is new.
+    pub fn new() -> ReplayWindow {
+        ReplayWindow { highest: 0, seen: 0 }
+    }
+
+    /// The highest sequence number seen, if any.
+    pub fn highest(&self) -> Option<u32> {
+        if self.seen == 0 { None } else { Some(self.highest) }
+    }
+
+    /// Check whether `seq` would be accepted, without marking it as
+    /// seen.
+    pub fn check(&self, seq: u32) -> Result<(), FrameError> {
+        if seq > self.highest {
+            return Ok(());
+        }
+        let behind = self.highest - seq;
+        if behind >= WINDOW_SIZE {
+            Err(FrameError::TooOld)
+        } else if self.seen & (1 << behind) != 0 {
+            Err(FrameError::Replay)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Mark `seq` as seen, if `check` accepts it.
+    pub fn accept(&mut self, seq: u32) -> Result<(), FrameError> {
+        self.check(seq)?;
+        if seq > self.highest {
+            let ahead = seq - self.highest;
+            self.seen = self.seen.checked_shl(ahead).unwrap_or(0);
+            self.highest = seq;
+        }
+        self.seen |= 1 << (self.highest - seq);
+        Ok(())
+    }
+}
+
+impl Default for ReplayWindow {
+    fn default() -> ReplayWindow {
+        ReplayWindow::new()
+    }
+}
+
+
+/// Errors from sealing or opening frames.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FrameError {
+    /// The payload doesn't fit the 16-bit length field.
+    PayloadTooLong,
+    /// The frame is too short to hold a header and a tag.
+    TooShort,
+    /// The frame has a key id the opener doesn't have.
+    UnknownKey(u8),
+    /// The frame's length doesn't match its length field.
+    Length,
+    /// The frame's sequence number has been seen already.
+    Replay,
+    /// The frame's sequence number is too far behind the window.
+    TooOld,
+    /// The tag doesn't match.
+    Authentication
+}
+
+impl fmt::Display for FrameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FrameError::PayloadTooLong => write!(f, "frame payload too long"),
+            FrameError::TooShort => write!(f, "frame too short"),
+            FrameError::UnknownKey(id) => write!(f, "unknown key id {}", id),
+            FrameError::Length => write!(f, "frame length doesn't match its header"),
+            FrameError::Replay => write!(f, "replayed frame"),
+            FrameError::TooOld => write!(f, "frame too old for the replay window"),
+            FrameError::Authentication => write!(f, "frame authentication failed")
+        }
+    }
+}
+
+impl Error for FrameError { }
+
+
+#[cfg(test)]
+mod tests {
+    use core::*;
+    use quickcheck::quickcheck;
+    use std::collections::HashSet;
+    use super::{FrameError, Opener, ReplayWindow, Sealer, HEADER_LEN, WINDOW_SIZE};
+    use Digester;
+
+    const KEY: [u32; 4] = [0x833D3433, 0x009F389F, 0x2398E64F, 0x417ACF39];
+
+    #[test]
+    fn layout() {
+        let mut sealer: Sealer<Chaskey> = Sealer::new(0xab, KEY, 6);
+        let frame = sealer.seal(0x01020304, b"hello").unwrap();
+        let header = [0xab, 1, 2, 3, 4, 0, 5];
+        assert_eq!(frame[..HEADER_LEN], header);
+        assert_eq!(&frame[HEADER_LEN..HEADER_LEN + 5], b"hello");
+
+        let mut d: Digester<Chaskey> = Digester::new(KEY);
+        d.write(&header);
+        d.write(b"hello");
+        assert_eq!(frame[HEADER_LEN + 5..], d.finish().raw_bytes()[..6]);
+    }
+
+    #[test]
+    fn delivery_8() {
+        delivery::<Chaskey>();
+    }
+
+    #[test]
+    fn delivery_12() {
+        delivery::<Chaskey12>();
+    }
+
+    #[test]
+    fn delivery_16() {
+        delivery::<ChaskeyLTS>();
+    }
+
+    /// Deliver 150 frames lost, duplicated and reordered as
+    /// `schedule` says (each entry is the index of a frame), and
+    /// check the opener against a simple model of the window.
+    fn delivery<P: Permutation>() {
+        fn prop<P: Permutation>(schedule: Vec<u8>) -> bool {
+            let mut sealer: Sealer<P> = Sealer::new(1, KEY, 8);
+            let mut opener: Opener<P> = Opener::new(1, KEY, 8);
+            let frames: Vec<Vec<u8>> = (0..150u32)
+                .map(|seq| sealer.seal(seq, &seq.to_le_bytes()).unwrap())
+                .collect();
+
+            let mut seen = HashSet::new();
+            let mut highest = None;
+            for &i in &schedule {
+                let seq = i as u32 % 150;
+                let expected = if seen.contains(&seq) {
+                    Err(FrameError::Replay)
+                } else if highest.is_some_and(|h| seq + WINDOW_SIZE <= h) {
+                    Err(FrameError::TooOld)
+                } else {
+                    seen.insert(seq);
+                    highest = highest.max(Some(seq));
+                    Ok(&frames[seq as usize][HEADER_LEN..HEADER_LEN + 4])
+                };
+                let result = opener.open(&frames[seq as usize]);
+                // A duplicate that has fallen out of the window is
+                // too old rather than a replay.
+                let ok = match (result, expected) {
+                    (Err(FrameError::TooOld), Err(FrameError::Replay)) =>
+                        seq + WINDOW_SIZE <= highest.unwrap(),
+                    (result, expected) => result == expected
+                };
+                if !ok || opener.window().highest() != highest {
+                    return false;
+                }
+            }
+            true
+        }
+        quickcheck(prop::<P> as fn(Vec<u8>) -> bool);
+    }
+
+    #[test]
+    fn window() {
+        let mut window = ReplayWindow::new();
+        assert_eq!(window.highest(), None);
+        assert_eq!(window.accept(0), Ok(()));
+        assert_eq!(window.accept(0), Err(FrameError::Replay));
+        assert_eq!(window.accept(100), Ok(()));
+        assert_eq!(window.check(0), Err(FrameError::TooOld));
+        assert_eq!(window.check(36), Err(FrameError::TooOld));
+        assert_eq!(window.accept(37), Ok(()));
+        assert_eq!(window.accept(99), Ok(()));
+        assert_eq!(window.accept(37), Err(FrameError::Replay));
+        assert_eq!(window.accept(u32::MAX), Ok(()));
+        assert_eq!(window.highest(), Some(u32::MAX));
+        assert_eq!(window.accept(u32::MAX), Err(FrameError::Replay));
+        assert_eq!(window.check(u32::MAX - 63), Ok(()));
+        assert_eq!(window.check(u32::MAX - 64), Err(FrameError::TooOld));
+    }
+
+    #[test]
+    fn errors() {
+        let mut sealer: Sealer<Chaskey> = Sealer::new(1, KEY, 4);
+        let mut opener: Opener<Chaskey> = Opener::new(1, KEY, 4);
+        assert_eq!(sealer.seal(0, &vec![0; 65536]), Err(FrameError::PayloadTooLong));
+        let frame = sealer.seal(5, b"payload").unwrap();
+
+        assert_eq!(opener.open(&frame[..10]), Err(FrameError::TooShort));
+        assert_eq!(opener.open(&frame[..frame.len() - 1]), Err(FrameError::Length));
+        let mut other: Opener<Chaskey> = Opener::new(2, KEY, 4);
+        assert_eq!(other.open(&frame), Err(FrameError::UnknownKey(1)));
+
+        // Tampering with any byte is caught, and doesn't use up the
+        // sequence number.
+        for i in 0..frame.len() {
+            let mut tampered = frame.clone();
+            tampered[i] ^= 0x10;
+            assert!(opener.open(&tampered).is_err());
+        }
+        assert_eq!(opener.window().highest(), None);
+        assert_eq!(opener.open(&frame), Ok(&b"payload"[..]));
+        assert_eq!(opener.open(&frame), Err(FrameError::Replay));
+    }
+
+    #[test]
+    #[should_panic]
+    fn short_tags() {
+        let _: Sealer<Chaskey> = Sealer::new(1, KEY, 3);
+    }
+}
@@ -0,0 +1,123 @@
+//! Hexadecimal encoding and decoding, as used by the crate's text
+//! formats.
+//!
+//! ```
+//! use chaskey::hex::{FromHex, ToHex};
+//!
+//! assert_eq!([0x01u8, 0xab].to_hex(), "01ab");
+//! assert_eq!("01AB".from_hex(), Ok(vec![0x01, 0xab]));
+//! assert!("01 ab".from_hex().is_err());
+//! ```
+//!
+//! Encoding gives lowercase digits.  Decoding accepts either case,
+//! but nothing else: unlike `rustc-serialize`, which this module
+//! replaces, whitespace is an error.
+
+use std::error::Error;
+use std::fmt;
+
+
+const DIGITS: &[u8; 16] = b"0123456789abcdef";
+
+/// Values that can be encoded as hexadecimal.
+pub trait ToHex {
+    /// Encode as lowercase hexadecimal, two digits per byte.
+    fn to_hex(&self) -> String;
+}
+
+impl ToHex for [u8] {
+    fn to_hex(&self) -> String {
+        let mut hex = String::with_capacity(2 * self.len());
+        for byte in self.iter() {
+            hex.push(DIGITS[(byte >> 4) as usize] as char);
+            hex.push(DIGITS[(byte & 0xf) as usize] as char);
+        }
+        hex
+    }
+}
+
+/// Values that can be decoded from hexadecimal.
+pub trait FromHex {
+    /// Decode hexadecimal digits, two per byte.
+    // Named like `rustc-serialize`'s method, which this replaces.
+    #[allow(clippy::wrong_self_convention)]
+    fn from_hex(&self) -> Result<Vec<u8>, FromHexError>;
+}
+
+impl FromHex for str {
+    fn from_hex(&self) -> Result<Vec<u8>, FromHexError> {
+        let digits = self.as_bytes();
+        let mut bytes = Vec::with_capacity(digits.len() / 2);
+        for (i, pair) in digits.chunks(2).enumerate() {
+            let hi = digit(self, 2 * i)?;
+            let lo = if pair.len() == 2 {
+                digit(self, 2 * i + 1)?
+            } else {
+                return Err(FromHexError::InvalidHexLength);
+            };
+            bytes.push(hi << 4 | lo);
+        }
+        Ok(bytes)
+    }
+}
+
+/// The value of the hex digit at byte offset `i` of `s`.
+fn digit(s: &str, i: usize) -> Result<u8, FromHexError> {
+    match s.as_bytes()[i] {
+        c @ b'0'..=b'9' => Ok(c - b'0'),
+        c @ b'a'..=b'f' => Ok(c - b'a' + 10),
+        c @ b'A'..=b'F' => Ok(c - b'A' + 10),
+        _ => {
+            // Report the whole (possibly multi-byte) character.
+            let c = s[i..].chars().next().unwrap_or('\u{fffd}');
+            Err(FromHexError::InvalidHexCharacter(c, i))
+        }
+    }
+}
+
+/// Errors from decoding hexadecimal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FromHexError {
+    /// The character at the given byte offset is not a hex digit.
+    InvalidHexCharacter(char, usize),
+    /// There is an odd number of digits.
+    InvalidHexLength
+}
+
+impl fmt::Display for FromHexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FromHexError::InvalidHexCharacter(c, i) =>
+                write!(f, "invalid hex character {:?} at offset {}", c, i),
+            FromHexError::InvalidHexLength =>
+                write!(f, "odd number of hex digits")
+        }
+    }
+}
+
+impl Error for FromHexError { }
+
+
+#[cfg(test)]
+mod tests {
+    use quickcheck::quickcheck;
+    use super::{FromHex, FromHexError, ToHex};
+
+    #[test]
+    fn round_trip() {
+        fn prop(bytes: Vec<u8>) -> bool {
+            bytes.to_hex().from_hex() == Ok(bytes.clone())
+                && bytes.to_hex().to_uppercase().from_hex() == Ok(bytes)
+        }
+        quickcheck(prop as fn(Vec<u8>) -> bool);
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!("".from_hex(), Ok(vec![]));
+        assert_eq!("abc".from_hex(), Err(FromHexError::InvalidHexLength));
+        assert_eq!("ag".from_hex(), Err(FromHexError::InvalidHexCharacter('g', 1)));
+        assert_eq!("00 1".from_hex(), Err(FromHexError::InvalidHexCharacter(' ', 2)));
+        assert_eq!("0é".from_hex(), Err(FromHexError::InvalidHexCharacter('é', 1)));
+    }
+}
@@ -0,0 +1,391 @@
+//! Known-answer test (KAT) vectors in a plain-text file format, so
+//! that implementations of Chaskey in other languages can check
+//! themselves against the same files as this crate.
+//!
+//! The format is modelled on NIST's `.rsp` files:
+//!
+//! ```text
+//! # Comments start with a hash sign.
+//!
+//! [chaskey-8 mac]
+//!
+//! COUNT = 0
+//! KEY = 33343d839f389f004fe6982339cf7a41
+//! MSG =
+//! TAG = e58f2e79aa87ce75b550142d0b979111
+//!
+//! [chaskey-lts cipher]
+//!
+//! COUNT = 0
+//! KEY = 5609e9685f58e32940ecec98c522982f
+//! PT = b8232826fd5e405e69a301a978ea7ad8
+//! CT = d5608d4da2bf347babf8772fdfedde07
+//! ```
+//!
+//! More precisely:
+//!
+//! * Blank lines and lines that start with `#` are ignored.
+//! * A section header `[VARIANT KIND]` says what the vectors that
+//!   follow it are for.  `VARIANT` is `chaskey-8`, `chaskey-12` or
+//!   `chaskey-lts`, and `KIND` is `mac` (the Chaskey MAC, with
+//!   untruncated 128-bit tags) or `cipher` (the Chaskey block
+//!   cipher).
+//! * Each vector is a group of `NAME = VALUE` lines, starting with a
+//!   `COUNT` line that numbers the vectors of the section from 0.
+//!   MAC vectors then have `KEY`, `MSG` and `TAG` lines, and cipher
+//!   vectors `KEY`, `PT` (plaintext) and `CT` (ciphertext) lines, in
+//!   that order.
+//! * All values other than `COUNT` are hexadecimal byte strings.
+//!   Keys, tags and blocks are 16 bytes long, and hold four 32-bit
+//!   words in little-endian order (the same layout as
+//!   `Tag::raw_bytes`).  Messages can have any length, including
+//!   zero.
+
+use core::{Chaskey, Chaskey12, ChaskeyLTS, Permutation, Variant};
+use cipher;
+use hex::{FromHex, ToHex};
+use std::error::Error;
+use std::fmt;
+use util::{read_u32x4, write_u32x4};
+use Digester;
+
+
+/// A known-answer test vector.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Vector {
+    /// A Chaskey MAC vector: `tag` is the tag of `message` under
+    /// `key`.
+    Mac {
+        variant: Variant,
+        key: [u32; 4],
+        message: Vec<u8>,
+        tag: [u32; 4]
+    },
+    /// A Chaskey block cipher vector: `ciphertext` is the encryption
+    /// of `plaintext` under `key`.
+    Cipher {
+        variant: Variant,
+        key: [u32; 4],
+        plaintext: [u32; 4],
+        ciphertext: [u32; 4]
+    }
+}
+
+impl Vector {
+    /// Make a MAC vector, computing the tag with this crate.
+    pub fn mac(variant: Variant, key: [u32; 4], message: Vec<u8>) -> Vector {
+        fn tag<P: Permutation>(key: [u32; 4], message: &[u8]) -> [u32; 4] {
+            let mut mac: Digester<P> = Digester::new(key);
+            mac.write(message);
+            *mac.finish().raw_words()
+        }
+        let tag = match variant {
+            Variant::Chaskey => tag::<Chaskey>(key, &message),
+            Variant::Chaskey12 => tag::<Chaskey12>(key, &message),
+            Variant::ChaskeyLTS => tag::<ChaskeyLTS>(key, &message)
+        };
+        Vector::Mac { variant, key, message, tag }
+    }
+
+    /// Make a block cipher vector, computing the ciphertext with this
+    /// crate.
+    pub fn cipher(variant: Variant, key: [u32; 4], plaintext: [u32; 4]) -> Vector {
+        let mut ciphertext = plaintext;
+        match variant {
+            Variant::Chaskey => cipher::encrypt::<Chaskey>(&mut ciphertext, &key),
+            Variant::Chaskey12 => cipher::encrypt::<Chaskey12>(&mut ciphertext, &key),
+            Variant::ChaskeyLTS => cipher::encrypt::<ChaskeyLTS>(&mut ciphertext, &key)
+        }
+        Vector::Cipher { variant, key, plaintext, ciphertext }
+    }
+
+    /// The variant the vector is for.
+    pub fn variant(&self) -> Variant {
+        match *self {
+            Vector::Mac { variant, .. } | Vector::Cipher { variant, .. } => variant
+        }
+    }
+
+    /// Check the vector against this crate's implementation.  Cipher
+    /// vectors are checked in both directions.
+    pub fn check(&self) -> bool {
+        match *self {
+            Vector::Mac { variant, key, ref message, .. } => {
+                *self == Vector::mac(variant, key, message.clone())
+            }
+            Vector::Cipher { variant, key, plaintext, ciphertext } => {
+                let mut decrypted = ciphertext;
+                match variant {
+                    Variant::Chaskey => cipher::decrypt::<Chaskey>(&mut decrypted, &key),
+                    Variant::Chaskey12 => cipher::decrypt::<Chaskey12>(&mut decrypted, &key),
+                    Variant::ChaskeyLTS => cipher::decrypt::<ChaskeyLTS>(&mut decrypted, &key)
+                }
+                *self == Vector::cipher(variant, key, plaintext) && decrypted == plaintext
+            }
+        }
+    }
+
+    /// The section header this vector goes under.
+    fn section(&self) -> String {
+        match *self {
+            Vector::Mac { variant, .. } => format!("[{} mac]", variant),
+            Vector::Cipher { variant, .. } => format!("[{} cipher]", variant)
+        }
+    }
+}
+
+
+/// Format vectors in the KAT file format.  Consecutive vectors of the
+/// same variant and kind go in the same section.
+pub fn format(vectors: &[Vector]) -> String {
+    let mut out = String::new();
+    let mut section = String::new();
+    let mut count = 0;
+    for vector in vectors.iter() {
+        if vector.section() != section {
+            if !out.is_empty() {
+                out.push('\n');
+            }
+            section = vector.section();
+            count = 0;
+            out.push_str(&section);
+            out.push('\n');
+        }
+        out.push_str(&format!("\nCOUNT = {}\n", count));
+        count += 1;
+        match *vector {
+            Vector::Mac { key, ref message, tag, .. } => {
+                out.push_str(&format!("KEY = {}\n", write_u32x4(&key).to_hex()));
+                if message.is_empty() {
+                    out.push_str("MSG =\n");
+                } else {
+                    out.push_str(&format!("MSG = {}\n", message.to_hex()));
+                }
+                out.push_str(&format!("TAG = {}\n", write_u32x4(&tag).to_hex()));
+            }
+            Vector::Cipher { key, plaintext, ciphertext, .. } => {
+                out.push_str(&format!("KEY = {}\n", write_u32x4(&key).to_hex()));
+                out.push_str(&format!("PT = {}\n", write_u32x4(&plaintext).to_hex()));
+                out.push_str(&format!("CT = {}\n", write_u32x4(&ciphertext).to_hex()));
+            }
+        }
+    }
+    out
+}
+
+/// Parse a KAT file.
+pub fn parse(text: &str) -> Result<Vec<Vector>, KatError> {
+    #[derive(Clone, Copy, PartialEq)]
+    enum Kind { Mac, Cipher }
+
+    let mut lines = text.lines().enumerate()
+        .map(|(n, line)| (n + 1, line.trim()))
+        .filter(|&(_, line)| !line.is_empty() && !line.starts_with('#'));
+    let mut vectors = Vec::new();
+    let mut section: Option<(Variant, Kind)> = None;
+    let mut count = 0;
+
+    while let Some((n, line)) = lines.next() {
+        if line.starts_with('[') {
+            let header = line.strip_prefix('[')
+                .and_then(|l| l.strip_suffix(']'))
+                .ok_or(KatError::Syntax(n))?;
+            let (variant, kind) = header.split_once(' ').ok_or(KatError::BadSection(n))?;
+            let variant = variant.parse().map_err(|_| KatError::BadSection(n))?;
+            let kind = match kind.trim() {
+                "mac" => Kind::Mac,
+                "cipher" => Kind::Cipher,
+                _ => return Err(KatError::BadSection(n))
+            };
+            section = Some((variant, kind));
+            count = 0;
+            continue;
+        }
+
+        let (variant, kind) = section.ok_or(KatError::MissingSection(n))?;
+        if field(n, line, "COUNT")? != count.to_string() {
+            return Err(KatError::BadValue(n, "COUNT"));
+        }
+        count += 1;
+
+        let mut next = |name| hex_field(&mut lines, name);
+        vectors.push(match kind {
+            Kind::Mac => Vector::Mac {
+                variant,
+                key: block(next("KEY")?)?,
+                message: next("MSG")?.2,
+                tag: block(next("TAG")?)?
+            },
+            Kind::Cipher => Vector::Cipher {
+                variant,
+                key: block(next("KEY")?)?,
+                plaintext: block(next("PT")?)?,
+                ciphertext: block(next("CT")?)?
+            }
+        });
+    }
+    Ok(vectors)
+}
+
+/// Read the next line, which must be a `NAME = VALUE` line with the
+/// given name and a hexadecimal value.  Returns the line number and
+/// the decoded value.
+fn hex_field<'a, I>(lines: &mut I, name: &'static str) -> Result<(usize, &'static str, Vec<u8>), KatError>
+    where I: Iterator<Item=(usize, &'a str)>
+{
+    let (n, line) = lines.next().ok_or(KatError::UnexpectedEnd(name))?;
+    let bytes = field(n, line, name)?.from_hex().map_err(|_| KatError::BadValue(n, name))?;
+    Ok((n, name, bytes))
+}
+
+/// Check that a field read by `hex_field` is a 16-byte block.
+fn block((n, name, bytes): (usize, &'static str, Vec<u8>)) -> Result<[u32; 4], KatError> {
+    if bytes.len() != 16 {
+        return Err(KatError::BadValue(n, name));
+    }
+    Ok(read_u32x4(&bytes))
+}
+
+/// The value of a `NAME = VALUE` line, which must have the given
+/// name.
+fn field<'a>(n: usize, line: &'a str, name: &'static str) -> Result<&'a str, KatError> {
+    match line.split_once('=') {
+        Some((found, value)) if found.trim() == name => Ok(value.trim()),
+        Some(_) => Err(KatError::ExpectedField(n, name)),
+        None => Err(KatError::Syntax(n))
+    }
+}
+
+
+/// The errors that can happen when parsing a KAT file.  Line numbers
+/// start at 1.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum KatError {
+    /// The line is neither a section header nor a `NAME = VALUE`
+    /// line.
+    Syntax(usize),
+    /// The section header names an unknown variant or kind.
+    BadSection(usize),
+    /// A vector comes before the first section header.
+    MissingSection(usize),
+    /// The line should have been the given field.
+    ExpectedField(usize, &'static str),
+    /// The field's value is malformed (or, for `COUNT`, out of
+    /// sequence).
+    BadValue(usize, &'static str),
+    /// The file ends in the middle of a vector, before the given
+    /// field.
+    UnexpectedEnd(&'static str)
+}
+
+impl fmt::Display for KatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            KatError::Syntax(n) =>
+                write!(f, "line {}: expected `[VARIANT KIND]` or `NAME = VALUE`", n),
+            KatError::BadSection(n) =>
+                write!(f, "line {}: unknown section", n),
+            KatError::MissingSection(n) =>
+                write!(f, "line {}: vector outside of any section", n),
+            KatError::ExpectedField(n, name) =>
+                write!(f, "line {}: expected `{}`", n, name),
+            KatError::BadValue(n, name) =>
+                write!(f, "line {}: invalid `{}` value", n, name),
+            KatError::UnexpectedEnd(name) =>
+                write!(f, "unexpected end of file; expected `{}`", name)
+        }
+    }
+}
+
+impl Error for KatError {}
+
+
+#[cfg(test)]
+mod tests {
+    use core::Variant;
+    use super::{format, parse, KatError, Vector};
+
+    const EXAMPLE: &str = "\
+# Comments start with a hash sign.
+
+[chaskey-8 mac]
+
+COUNT = 0
+KEY = 33343d839f389f004fe6982339cf7a41
+MSG =
+TAG = e58f2e79aa87ce75b550142d0b979111
+
+[chaskey-lts cipher]
+
+COUNT = 0
+KEY = 5609e9685f58e32940ecec98c522982f
+PT = b8232826fd5e405e69a301a978ea7ad8
+CT = d5608d4da2bf347babf8772fdfedde07
+";
+
+    /// The example in the module documentation has the first 8-round
+    /// test vector and the FELICS Chaskey-LTS triple.
+    #[test]
+    fn parse_example() {
+        let vectors = parse(EXAMPLE).unwrap();
+        assert_eq!(vectors, vec![
+            Vector::Mac {
+                variant: Variant::Chaskey,
+                key: [0x833D3433, 0x009F389F, 0x2398E64F, 0x417ACF39],
+                message: vec![],
+                tag: [0x792E8FE5, 0x75CE87AA, 0x2D1450B5, 0x1191970B]
+            },
+            Vector::Cipher {
+                variant: Variant::ChaskeyLTS,
+                key: [0x68e90956, 0x29e3585f, 0x98ecec40, 0x2f9822c5],
+                plaintext: [0x262823b8, 0x5e405efd, 0xa901a369, 0xd87aea78],
+                ciphertext: [0x4d8d60d5, 0x7b34bfa2, 0x2f77f8ab, 0x07deeddf]
+            }
+        ]);
+        assert!(vectors.iter().all(Vector::check));
+        assert_eq!(format(&vectors), EXAMPLE.replace("# Comments start with a hash sign.\n\n", ""));
+    }
+
+    #[test]
+    fn round_trip() {
+        let vectors = vec![
+            Vector::mac(Variant::Chaskey12, [1, 2, 3, 4], b"abc".to_vec()),
+            Vector::mac(Variant::Chaskey12, [5, 6, 7, 8], vec![0xff; 40]),
+            Vector::cipher(Variant::Chaskey, [1, 2, 3, 4], [5, 6, 7, 8]),
+            Vector::mac(Variant::ChaskeyLTS, [1, 2, 3, 4], vec![])
+        ];
+        assert_eq!(parse(&format(&vectors)), Ok(vectors));
+    }
+
+    #[test]
+    fn wrong_answers() {
+        let mut vectors = parse(EXAMPLE).unwrap();
+        if let Vector::Mac { ref mut message, .. } = vectors[0] {
+            message.push(0);
+        }
+        if let Vector::Cipher { ref mut ciphertext, .. } = vectors[1] {
+            ciphertext[3] ^= 1;
+        }
+        assert!(!vectors[0].check());
+        assert!(!vectors[1].check());
+    }
+
+    #[test]
+    fn errors() {
+        fn edit(from: &str, to: &str) -> Result<Vec<Vector>, KatError> {
+            parse(&EXAMPLE.replacen(from, to, 1))
+        }
+        assert_eq!(edit("[chaskey-8 mac]", "[chaskey-9 mac]"), Err(KatError::BadSection(3)));
+        assert_eq!(edit("[chaskey-8 mac]", "[chaskey-8 hash]"), Err(KatError::BadSection(3)));
+        assert_eq!(edit("[chaskey-8 mac]", "[chaskey-8 mac"), Err(KatError::Syntax(3)));
+        assert_eq!(edit("[chaskey-8 mac]", ""), Err(KatError::MissingSection(5)));
+        assert_eq!(edit("COUNT = 0", "COUNT = 1"), Err(KatError::BadValue(5, "COUNT")));
+        assert_eq!(edit("MSG =", "MSG"), Err(KatError::Syntax(7)));
+        assert_eq!(edit("MSG =", "TAG ="), Err(KatError::ExpectedField(7, "MSG")));
+        assert_eq!(edit("MSG =", "MSG = 0"), Err(KatError::BadValue(7, "MSG")));
+        assert_eq!(edit("TAG = e5", "TAG = "), Err(KatError::BadValue(8, "TAG")));
+        assert_eq!(edit("PT = b8", "PT = xx"), Err(KatError::BadValue(14, "PT")));
+        assert_eq!(parse(&EXAMPLE[..EXAMPLE.find("CT =").unwrap()]),
+                   Err(KatError::UnexpectedEnd("CT")));
+    }
+}
@@ -0,0 +1,458 @@
+//! A plain-text file format for storing Chaskey keys.
+//!
+//! A key file looks like this:
+//!
+//! ```text
+//! # Chaskey key file.  Keep it secret!
+//! chaskey-key: 1
+//! variant: chaskey-12
+//! id: gateway-2026-10
+//! key: 33343d839f389f004fe6982339cf7a41
+//! check: ca3a78d0
+//! ```
+//!
+//! The rules are:
+//!
+//! * Blank lines, and lines whose first non-blank character is `#`,
+//!   are ignored.
+//! * Every other line is a `name: value` field.  Whitespace around
+//!   names and values is ignored.
+//! * The first field must be `chaskey-key`, the format version, which
+//!   is `1`.
+//! * The `variant`, `key` and `check` fields are required, the `id`
+//!   field is optional, and no field may appear twice.  The order of
+//!   the fields after `chaskey-key` doesn't matter.
+//! * `variant` is one of `chaskey-8`, `chaskey-12` or `chaskey-lts`.
+//! * `id` is the key identifier, 1 to 64 printable ASCII characters
+//!   other than space.
+//! * `key` is the key, as 32 hexadecimal digits.  These encode 16
+//!   bytes, which are the key's four 32-bit words in little-endian
+//!   order.
+//! * `check` is a checksum over the rest of the record, as 8
+//!   hexadecimal digits.  It is the first 4 bytes of the Chaskey tag,
+//!   computed with the key and variant in the file, of the message
+//!   `"chaskey-key-check" || 0x00 || variant || 0x00 || id` (with the
+//!   id empty if absent).  It catches typos and mix-ups, and doesn't
+//!   give away the key.
+//!
+//! ## Example
+//!
+//! ```
+//! # extern crate chaskey;
+//! use chaskey::Variant;
+//! use chaskey::keyfile::KeyFile;
+//!
+//! # fn main() {
+//! let file = KeyFile::new(Variant::Chaskey12, [1, 2, 3, 4], Some("my-key")).unwrap();
+//! let text = file.to_string();
+//! let parsed: KeyFile = text.parse().unwrap();
+//! assert_eq!(parsed.key(), &[1, 2, 3, 4]);
+//! assert_eq!(parsed.id(), Some("my-key"));
+//! # }
+//! ```
+
+use core::{Chaskey, Chaskey12, ChaskeyLTS, Permutation, Variant};
+use hex::{FromHex, ToHex};
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+use subtle::{Choice, ConstantTimeEq};
+use util::{read_u32x4, write_u32x4};
+use zeroize::Zeroize;
+use {Digester, Tag};
+
+
+/// The version of the format this module reads and writes.
+const VERSION: &str = "1";
+
+/// The maximum length of a key identifier.
+const MAX_ID_LEN: usize = 64;
+
+
+/// The contents of a key file: a key, the Chaskey variant it is
+/// meant for, and optionally an identifier.
+///
+/// Equality compares the keys in constant time (the variant and
+/// identifier are not secret); `KeyFile` also implements
+/// `subtle::ConstantTimeEq`.  It can't implement
+/// `ConditionallySelectable`, which requires `Copy`, because it owns
+/// its identifier and wipes its key when dropped; select the keys
+/// with `<[u32; 4]>::conditional_select` instead.
+#[derive(Clone)]
+pub struct KeyFile {
+    variant: Variant,
+    key: [u32; 4],
+    id: Option<String>
+}
+
+impl KeyFile {
+    /// Make a key file record.  This fails only if `id` is not a
+    /// valid key identifier.
+    pub fn new(variant: Variant, key: [u32; 4], id: Option<&str>)
+               -> Result<KeyFile, KeyFileError> {
+        if let Some(id) = id {
+            if !valid_id(id) {
+                return Err(KeyFileError::InvalidId);
+            }
+        }
+        Ok(KeyFile {
+            variant,
+            key,
+            id: id.map(String::from)
+        })
+    }
+
+    /// The Chaskey variant the key is meant for.
+    pub fn variant(&self) -> Variant {
+        self.variant
+    }
+
+    /// The key, in the form `Digester::new` takes.
+    pub fn key(&self) -> &[u32; 4] {
+        &self.key
+    }
+
+    /// The key identifier, if there is one.
+    pub fn id(&self) -> Option<&str> {
+        self.id.as_ref().map(|id| &id[..])
+    }
+
+    /// The checksum recorded in the `check` field.
+    pub fn check_value(&self) -> [u8; 4] {
+        fn tag<P: Permutation>(file: &KeyFile) -> Tag {
+            let mut mac: Digester<P> = Digester::new(file.key);
+            mac.write(b"chaskey-key-check\0");
+            mac.write(file.variant.name().as_bytes());
+            mac.write(b"\0");
+            mac.write(file.id().unwrap_or("").as_bytes());
+            mac.finish()
+        }
+        let tag = match self.variant {
+            Variant::Chaskey => tag::<Chaskey>(self),
+            Variant::Chaskey12 => tag::<Chaskey12>(self),
+            Variant::ChaskeyLTS => tag::<ChaskeyLTS>(self)
+        };
+        let bytes = tag.raw_bytes();
+        [bytes[0], bytes[1], bytes[2], bytes[3]]
+    }
+}
+
+/// Wipe the key.
+impl Drop for KeyFile {
+    fn drop(&mut self) {
+        self.key.zeroize();
+    }
+}
+
+impl ConstantTimeEq for KeyFile {
+    fn ct_eq(&self, other: &KeyFile) -> Choice {
+        let public = self.variant == other.variant && self.id == other.id;
+        Choice::from(public as u8) & self.key[..].ct_eq(&other.key[..])
+    }
+}
+
+impl PartialEq for KeyFile {
+    fn eq(&self, other: &KeyFile) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl Eq for KeyFile { }
+
+/// Doesn't show the key.
+impl fmt::Debug for KeyFile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("KeyFile")
+            .field("variant", &self.variant)
+            .field("id", &self.id)
+            .finish()
+    }
+}
+
+/// Serializes the record in the key file format.
+impl fmt::Display for KeyFile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "# Chaskey key file.  Keep it secret!")?;
+        writeln!(f, "chaskey-key: {}", VERSION)?;
+        writeln!(f, "variant: {}", self.variant)?;
+        if let Some(ref id) = self.id {
+            writeln!(f, "id: {}", id)?;
+        }
+        writeln!(f, "key: {}", write_u32x4(&self.key).to_hex())?;
+        writeln!(f, "check: {}", self.check_value().to_hex())
+    }
+}
+
+/// Parses the key file format.
+impl FromStr for KeyFile {
+    type Err = KeyFileError;
+
+    fn from_str(s: &str) -> Result<KeyFile, KeyFileError> {
+        let mut version = false;
+        let mut variant: Option<Variant> = None;
+        let mut id: Option<String> = None;
+        let mut key: Option<[u32; 4]> = None;
+        let mut check: Option<Vec<u8>> = None;
+
+        for (n, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (name, value) = match line.split_once(':') {
+                Some((name, value)) => (name.trim(), value.trim()),
+                None => return Err(KeyFileError::Syntax(n + 1))
+            };
+            if !version {
+                if name != "chaskey-key" {
+                    return Err(KeyFileError::MissingHeader);
+                }
+                if value != VERSION {
+                    return Err(KeyFileError::UnsupportedVersion(value.to_string()));
+                }
+                version = true;
+                continue;
+            }
+            let duplicate = match name {
+                "variant" => variant.replace(
+                    value.parse().map_err(|_| KeyFileError::InvalidVariant)?
+                ).is_some(),
+                "id" => {
+                    if !valid_id(value) {
+                        return Err(KeyFileError::InvalidId);
+                    }
+                    id.replace(value.to_string()).is_some()
+                }
+                "key" => key.replace(parse_key(value)?).is_some(),
+                "check" => check.replace(parse_hex(value, 4)
+                                         .ok_or(KeyFileError::InvalidCheck)?).is_some(),
+                _ => return Err(KeyFileError::UnknownField(name.to_string()))
+            };
+            if duplicate {
+                return Err(KeyFileError::DuplicateField(name.to_string()));
+            }
+        }
+
+        if !version {
+            return Err(KeyFileError::MissingHeader);
+        }
+        let file = KeyFile {
+            variant: variant.ok_or(KeyFileError::MissingField("variant"))?,
+            key: key.ok_or(KeyFileError::MissingField("key"))?,
+            id
+        };
+        let check = check.ok_or(KeyFileError::MissingField("check"))?;
+        if check[..] != file.check_value()[..] {
+            return Err(KeyFileError::ChecksumMismatch);
+        }
+        Ok(file)
+    }
+}
+
+/// Parse a bare key, given as 32 hexadecimal digits encoding its
+/// words in little-endian order (the same encoding as the `key` field
+/// of a key file).
+pub fn parse_key(s: &str) -> Result<[u32; 4], KeyFileError> {
+    parse_hex(s.trim(), 16)
+        .map(|bytes| read_u32x4(&bytes))
+        .ok_or(KeyFileError::InvalidKey)
+}
+
+/// Format a bare key in the encoding `parse_key` reads.
+pub fn format_key(key: &[u32; 4]) -> String {
+    write_u32x4(key).to_hex()
+}
+
+fn parse_hex(s: &str, len: usize) -> Option<Vec<u8>> {
+    if s.len() != 2 * len {
+        return None;
+    }
+    s.from_hex().ok()
+}
+
+fn valid_id(id: &str) -> bool {
+    !id.is_empty() && id.len() <= MAX_ID_LEN && id.bytes().all(|b| b.is_ascii_graphic())
+}
+
+
+/// The errors that can happen when reading a key file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum KeyFileError {
+    /// The file doesn't start with a `chaskey-key` field.
+    MissingHeader,
+    /// The file is in a version of the format we don't know.
+    UnsupportedVersion(String),
+    /// The line with this (1-based) number is not a `name: value`
+    /// field.
+    Syntax(usize),
+    /// A field we don't know.
+    UnknownField(String),
+    /// A field that appears more than once.
+    DuplicateField(String),
+    /// A required field that is missing.
+    MissingField(&'static str),
+    /// The `variant` field is not a known variant.
+    InvalidVariant,
+    /// The key identifier is empty, too long or has characters that
+    /// are not allowed.
+    InvalidId,
+    /// The key is not 32 hexadecimal digits.
+    InvalidKey,
+    /// The `check` field is not 8 hexadecimal digits.
+    InvalidCheck,
+    /// The checksum doesn't match the rest of the record.
+    ChecksumMismatch
+}
+
+impl fmt::Display for KeyFileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            KeyFileError::MissingHeader =>
+                write!(f, "not a Chaskey key file (no `chaskey-key` header)"),
+            KeyFileError::UnsupportedVersion(ref v) =>
+                write!(f, "unsupported key file version `{}`", v),
+            KeyFileError::Syntax(line) =>
+                write!(f, "line {}: expected `name: value`", line),
+            KeyFileError::UnknownField(ref name) =>
+                write!(f, "unknown field `{}`", name),
+            KeyFileError::DuplicateField(ref name) =>
+                write!(f, "duplicate field `{}`", name),
+            KeyFileError::MissingField(name) =>
+                write!(f, "missing field `{}`", name),
+            KeyFileError::InvalidVariant =>
+                write!(f, "invalid variant (expected chaskey-8, chaskey-12 or chaskey-lts)"),
+            KeyFileError::InvalidId =>
+                write!(f, "invalid key id (expected 1 to {} printable ASCII characters)",
+                       MAX_ID_LEN),
+            KeyFileError::InvalidKey =>
+                write!(f, "invalid key (expected 32 hexadecimal digits)"),
+            KeyFileError::InvalidCheck =>
+                write!(f, "invalid check value (expected 8 hexadecimal digits)"),
+            KeyFileError::ChecksumMismatch =>
+                write!(f, "checksum mismatch; the key file is corrupted")
+        }
+    }
+}
+
+impl Error for KeyFileError {}
+
+
+#[cfg(test)]
+mod tests {
+    use core::Variant;
+    use super::{KeyFile, KeyFileError, parse_key};
+    use std::mem::ManuallyDrop;
+    use std::ptr;
+
+    const KEY: [u32; 4] = [0x833D3433, 0x009F389F, 0x2398E64F, 0x417ACF39];
+
+    const EXAMPLE: &str = "\
+# Chaskey key file.  Keep it secret!
+chaskey-key: 1
+variant: chaskey-12
+id: gateway-2026-10
+key: 33343d839f389f004fe6982339cf7a41
+check: ca3a78d0
+";
+
+    #[test]
+    fn parse_example() {
+        let file: KeyFile = EXAMPLE.parse().unwrap();
+        assert_eq!(file.variant(), Variant::Chaskey12);
+        assert_eq!(file.key(), &KEY);
+        assert_eq!(file.id(), Some("gateway-2026-10"));
+        assert_eq!(file.to_string(), EXAMPLE);
+    }
+
+    #[test]
+    fn round_trip() {
+        for &variant in [Variant::Chaskey, Variant::Chaskey12, Variant::ChaskeyLTS].iter() {
+            for &id in [None, Some("k1")].iter() {
+                let file = KeyFile::new(variant, KEY, id).unwrap();
+                assert_eq!(file.to_string().parse(), Ok(file));
+            }
+        }
+    }
+
+    #[test]
+    fn field_order_and_whitespace() {
+        let text = "\n  chaskey-key:1\r\n\
+                    check :  ca3a78d0\n\
+                    \t# comment\n\
+                    key: 33343D839F389F004FE6982339CF7A41\n\
+                    id: gateway-2026-10\n\
+                    variant: 12\n";
+        assert_eq!(text.parse(), EXAMPLE.parse::<KeyFile>());
+    }
+
+    #[test]
+    fn errors() {
+        fn parse(s: &str) -> Result<KeyFile, KeyFileError> {
+            s.parse()
+        }
+        fn edit(from: &str, to: &str) -> Result<KeyFile, KeyFileError> {
+            parse(&EXAMPLE.replace(from, to))
+        }
+        assert_eq!(parse(""), Err(KeyFileError::MissingHeader));
+        assert_eq!(edit("chaskey-key: 1\n", ""), Err(KeyFileError::MissingHeader));
+        assert_eq!(edit("chaskey-key: 1", "chaskey-key: 2"),
+                   Err(KeyFileError::UnsupportedVersion("2".to_string())));
+        assert_eq!(edit("id: ", "id "), Err(KeyFileError::Syntax(4)));
+        assert_eq!(edit("id: ", "name: "),
+                   Err(KeyFileError::UnknownField("name".to_string())));
+        assert_eq!(edit("id: gateway-2026-10", "variant: 8"),
+                   Err(KeyFileError::DuplicateField("variant".to_string())));
+        assert_eq!(edit("check: ca3a78d0\n", ""), Err(KeyFileError::MissingField("check")));
+        assert_eq!(edit("chaskey-12", "chaskey-13"), Err(KeyFileError::InvalidVariant));
+        assert_eq!(edit("gateway-2026-10", "gate way"), Err(KeyFileError::InvalidId));
+        assert_eq!(edit("7a41", "7a4"), Err(KeyFileError::InvalidKey));
+        assert_eq!(edit("7a41", "7a4g"), Err(KeyFileError::InvalidKey));
+        assert_eq!(edit("ca3a78d0", "ca3a78"), Err(KeyFileError::InvalidCheck));
+
+        // Any change to the variant, id or key is caught by the
+        // checksum.
+        assert_eq!(edit("chaskey-12", "chaskey-8"), Err(KeyFileError::ChecksumMismatch));
+        assert_eq!(edit("2026-10", "2026-11"), Err(KeyFileError::ChecksumMismatch));
+        assert_eq!(edit("id: gateway-2026-10\n", ""), Err(KeyFileError::ChecksumMismatch));
+        assert_eq!(edit("7a41", "7a42"), Err(KeyFileError::ChecksumMismatch));
+    }
+
+    #[test]
+    fn invalid_ids() {
+        assert_eq!(KeyFile::new(Variant::Chaskey, KEY, Some("")).err(),
+                   Some(KeyFileError::InvalidId));
+        assert_eq!(KeyFile::new(Variant::Chaskey, KEY, Some(&"x".repeat(65))).err(),
+                   Some(KeyFileError::InvalidId));
+        assert!(KeyFile::new(Variant::Chaskey, KEY, Some(&"x".repeat(64))).is_ok());
+    }
+
+    #[test]
+    fn equality() {
+        let file = KeyFile::new(Variant::Chaskey, KEY, Some("a")).unwrap();
+        let mut other_key = KEY;
+        other_key[3] ^= 1;
+        assert!(file == file.clone());
+        assert!(file != KeyFile::new(Variant::Chaskey, other_key, Some("a")).unwrap());
+        assert!(file != KeyFile::new(Variant::Chaskey12, KEY, Some("a")).unwrap());
+        assert!(file != KeyFile::new(Variant::Chaskey, KEY, None).unwrap());
+    }
+
+    #[test]
+    fn zeroize_on_drop() {
+        let mut file = ManuallyDrop::new(KeyFile::new(Variant::Chaskey, KEY, None).unwrap());
+        let key = file.key().as_ptr();
+        unsafe {
+            ManuallyDrop::drop(&mut file);
+            for i in 0..4 {
+                assert_eq!(ptr::read_volatile(key.add(i)), 0);
+            }
+        }
+    }
+
+    #[test]
+    fn bare_keys() {
+        assert_eq!(parse_key(" 33343d839f389f004fe6982339cf7a41\n"), Ok(KEY));
+        assert_eq!(parse_key("33343d839f389f004fe6982339cf7a"), Err(KeyFileError::InvalidKey));
+        assert_eq!(parse_key("33343d839f389f004fe69823\t9cf7a41"), Err(KeyFileError::InvalidKey));
+    }
+}
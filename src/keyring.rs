@@ -0,0 +1,481 @@
+//! A set of MAC keys with identifiers, for rotating keys without
+//! downtime.
+//!
+//! Every key in a `Keyring` has a one-byte identifier, a `Variant`,
+//! and a state:
+//!
+//! * `Active`: the key that signs.  There is at most one.
+//! * `Valid`: the key verifies tags, but doesn't sign.  This is how a
+//!   new key is installed on verifiers ahead of a rollover.
+//! * `ValidUntil(t)`: the key verifies tags until time `t` (seconds
+//!   since the Unix epoch, by the keyring's `Clock`).  This is how an
+//!   old key is phased out after a rollover.
+//!
+//! Signing gives a `KeyedTag`, which carries the id of the key that
+//! made it, so verifiers know which key to check it with.  For tags
+//! that come without an id, `verify_any` tries every valid key.
+//!
+//! ```
+//! # extern crate chaskey;
+//! use chaskey::Variant;
+//! use chaskey::clock::ManualClock;
+//! use chaskey::keyring::{Keyring, KeyringError};
+//!
+//! # fn main() {
+//! let clock = ManualClock::new(1_000_000);
+//! let mut keyring = Keyring::with_clock(&clock);
+//! keyring.insert(1, Variant::Chaskey12, [1, 2, 3, 4]).unwrap();
+//! keyring.activate(1).unwrap();
+//! let old = keyring.sign(b"message").unwrap();
+//!
+//! // Switch to key 2; key 1 stays valid for a day.
+//! keyring.rotate(2, Variant::Chaskey12, [5, 6, 7, 8], 86400).unwrap();
+//! let new = keyring.sign(b"message").unwrap();
+//! assert_eq!(new.key_id(), 2);
+//! assert_eq!(keyring.verify(b"message", &old), Ok(()));
+//!
+//! clock.advance(86400);
+//! assert_eq!(keyring.verify(b"message", &old), Err(KeyringError::Expired(1)));
+//! assert_eq!(keyring.verify(b"message", &new), Ok(()));
+//! # }
+//! ```
+
+use clock::{Clock, SystemClock};
+use core::{Chaskey, Chaskey12, ChaskeyLTS, Permutation, Variant};
+use hex::ToHex;
+use keyfile::KeyFile;
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+use subtle::{Choice, ConditionallySelectable, ConstantTimeEq};
+use super::{decode_hex_tag, make_keys, Digester, Keys, ParseTagError, Tag};
+
+
+/// What a key in a `Keyring` may be used for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyState {
+    /// Signs and verifies.
+    Active,
+    /// Verifies.
+    Valid,
+    /// Verifies until the given time.
+    ValidUntil(u64)
+}
+
+struct Entry {
+    variant: Variant,
+    keys: Keys,
+    state: KeyState
+}
+
+impl Entry {
+    fn is_valid(&self, now: u64) -> bool {
+        match self.state {
+            KeyState::ValidUntil(until) => now < until,
+            _ => true
+        }
+    }
+
+    fn tag(&self, msg: &[u8]) -> Tag {
+        fn tag<P: Permutation>(keys: &Keys, msg: &[u8]) -> Tag {
+            let mut digester: Digester<P> = Digester::from_keys(keys.clone());
+            digester.write(msg);
+            digester.finish()
+        }
+        match self.variant {
+            Variant::Chaskey => tag::<Chaskey>(&self.keys, msg),
+            Variant::Chaskey12 => tag::<Chaskey12>(&self.keys, msg),
+            Variant::ChaskeyLTS => tag::<ChaskeyLTS>(&self.keys, msg)
+        }
+    }
+}
+
+
+/// A set of keys, each with an id, a variant and a state.
+///
+/// The key schedules are wiped when the keys are removed or the
+/// keyring is dropped.
+pub struct Keyring<C = SystemClock> {
+    clock: C,
+    entries: BTreeMap<u8, Entry>,
+    active: Option<u8>
+}
+
+impl Keyring<SystemClock> {
+    /// An empty keyring that uses the system clock.
+    pub fn new() -> Keyring<SystemClock> {
+        Keyring::with_clock(SystemClock)
+    }
+}
+
+impl Default for Keyring<SystemClock> {
+    fn default() -> Keyring<SystemClock> {
+        Keyring::new()
+    }
+}
+
+impl<C: Clock> Keyring<C> {
+    /// An empty keyring that uses `clock` to tell when keys expire.
+    pub fn with_clock(clock: C) -> Keyring<C> {
+        Keyring { clock, entries: BTreeMap::new(), active: None }
+    }
+
+    /// Add a key, in the `Valid` state.
+    pub fn insert(&mut self, id: u8, variant: Variant, key: [u32; 4]) -> Result<(), KeyringError> {
+        if self.entries.contains_key(&id) {
+            return Err(KeyringError::DuplicateKey(id));
+        }
+        let entry = Entry { variant, keys: make_keys(key), state: KeyState::Valid };
+        self.entries.insert(id, entry);
+        Ok(())
+    }
+
+    /// Add the key in a key file, in the `Valid` state.
+    pub fn insert_key_file(&mut self, id: u8, file: &KeyFile) -> Result<(), KeyringError> {
+        self.insert(id, file.variant(), *file.key())
+    }
+
+    /// Remove a key.  Returns whether it was there.
+    pub fn remove(&mut self, id: u8) -> bool {
+        if self.active == Some(id) {
+            self.active = None;
+        }
+        self.entries.remove(&id).is_some()
+    }
+
+    /// Remove the keys that have expired.
+    pub fn prune(&mut self) {
+        let now = self.clock.now();
+        self.entries.retain(|_, entry| entry.is_valid(now));
+    }
+
+    /// The state of a key, or `None` if there is no such key.  Keys
+    /// keep their `ValidUntil` state after they expire, until pruned.
+    pub fn state(&self, id: u8) -> Option<KeyState> {
+        self.entries.get(&id).map(|entry| entry.state)
+    }
+
+    /// The id of the active key, if there is one.
+    pub fn active_id(&self) -> Option<u8> {
+        self.active
+    }
+
+    /// The ids of all the keys, expired or not, in order.
+    pub fn ids(&self) -> Vec<u8> {
+        self.entries.keys().cloned().collect()
+    }
+
+    /// Make a key the active one.  The key that was active before,
+    /// if any, becomes `Valid`.  Fails if the key is missing or
+    /// expired.
+    pub fn activate(&mut self, id: u8) -> Result<(), KeyringError> {
+        self.valid_entry(id)?;
+        if let Some(old) = self.active.take() {
+            self.entries.get_mut(&old).unwrap().state = KeyState::Valid;
+        }
+        self.entries.get_mut(&id).unwrap().state = KeyState::Active;
+        self.active = Some(id);
+        Ok(())
+    }
+
+    /// Let a key verify only until time `until`.  If it was the
+    /// active key, there is no active key afterwards.
+    pub fn retire(&mut self, id: u8, until: u64) -> Result<(), KeyringError> {
+        let entry = self.entries.get_mut(&id).ok_or(KeyringError::UnknownKey(id))?;
+        entry.state = KeyState::ValidUntil(until);
+        if self.active == Some(id) {
+            self.active = None;
+        }
+        Ok(())
+    }
+
+    /// Roll over to a new key: add it and make it active, and let the
+    /// key that was active verify for `grace` more seconds.
+    pub fn rotate(&mut self, id: u8, variant: Variant, key: [u32; 4], grace: u64)
+                  -> Result<(), KeyringError> {
+        self.insert(id, variant, key)?;
+        if let Some(old) = self.active {
+            let until = self.clock.now().saturating_add(grace);
+            self.retire(old, until)?;
+        }
+        self.activate(id)
+    }
+
+    /// Sign a message with the active key.
+    pub fn sign(&self, msg: &[u8]) -> Result<KeyedTag, KeyringError> {
+        let id = self.active.ok_or(KeyringError::NoActiveKey)?;
+        Ok(KeyedTag { key_id: id, tag: self.entries[&id].tag(msg) })
+    }
+
+    /// Verify a keyed tag with the key it names.
+    pub fn verify(&self, msg: &[u8], tag: &KeyedTag) -> Result<(), KeyringError> {
+        let entry = self.valid_entry(tag.key_id)?;
+        if entry.tag(msg) == tag.tag {
+            Ok(())
+        } else {
+            Err(KeyringError::Authentication)
+        }
+    }
+
+    /// Verify a tag that doesn't name its key, by trying every valid
+    /// key, and return the id of the one that made it.  Every valid
+    /// key is tried whether or not an earlier one matched, and the
+    /// comparisons are constant-time, so the time taken depends only
+    /// on the number of valid keys.
+    pub fn verify_any(&self, msg: &[u8], tag: &Tag) -> Result<u8, KeyringError> {
+        let now = self.clock.now();
+        let mut found = Choice::from(0);
+        let mut found_id = 0u8;
+        for (&id, entry) in self.entries.iter().filter(|&(_, entry)| entry.is_valid(now)) {
+            let matches = entry.tag(msg).ct_eq(tag);
+            found_id.conditional_assign(&id, matches);
+            found |= matches;
+        }
+        if bool::from(found) {
+            Ok(found_id)
+        } else {
+            Err(KeyringError::Authentication)
+        }
+    }
+
+    fn valid_entry(&self, id: u8) -> Result<&Entry, KeyringError> {
+        let entry = self.entries.get(&id).ok_or(KeyringError::UnknownKey(id))?;
+        if entry.is_valid(self.clock.now()) {
+            Ok(entry)
+        } else {
+            Err(KeyringError::Expired(id))
+        }
+    }
+}
+
+
+/// A tag together with the id of the key that made it.
+///
+/// As bytes, it is the key id followed by the tag's 16 bytes; as
+/// text, the hex of those 17 bytes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeyedTag {
+    key_id: u8,
+    tag: Tag
+}
+
+impl KeyedTag {
+    /// A tag made by the key with id `key_id`.
+    pub fn new(key_id: u8, tag: Tag) -> KeyedTag {
+        KeyedTag { key_id, tag }
+    }
+
+    /// The id of the key that made the tag.
+    pub fn key_id(&self) -> u8 {
+        self.key_id
+    }
+
+    /// The tag itself.
+    pub fn tag(&self) -> Tag {
+        self.tag
+    }
+
+    /// The key id, then the tag's `raw_bytes`.
+    pub fn to_bytes(&self) -> [u8; 17] {
+        let mut bytes = [0u8; 17];
+        bytes[0] = self.key_id;
+        bytes[1..].copy_from_slice(&self.tag.raw_bytes());
+        bytes
+    }
+}
+
+/// The inverse of `to_bytes`.
+impl TryFrom<&[u8]> for KeyedTag {
+    type Error = ParseTagError;
+
+    fn try_from(bytes: &[u8]) -> Result<KeyedTag, ParseTagError> {
+        if bytes.len() != 17 {
+            return Err(ParseTagError::InvalidLength(bytes.len()));
+        }
+        Ok(KeyedTag { key_id: bytes[0], tag: Tag::try_from(&bytes[1..])? })
+    }
+}
+
+/// Lowercase hex of `to_bytes`.
+impl fmt::Display for KeyedTag {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad(&self.to_bytes().to_hex())
+    }
+}
+
+/// Parse 34 hex digits, in the order of `to_bytes`.
+impl FromStr for KeyedTag {
+    type Err = ParseTagError;
+
+    fn from_str(s: &str) -> Result<KeyedTag, ParseTagError> {
+        let bytes = decode_hex_tag(s)?;
+        KeyedTag::try_from(&bytes[..]).map_err(|_| ParseTagError::InvalidLength(s.len()))
+    }
+}
+
+
+/// Errors from managing a `Keyring` or verifying with it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyringError {
+    /// A key with this id is already in the keyring.
+    DuplicateKey(u8),
+    /// There is no key with this id.
+    UnknownKey(u8),
+    /// The key with this id has expired.
+    Expired(u8),
+    /// There is no active key to sign with.
+    NoActiveKey,
+    /// The tag doesn't match.
+    Authentication
+}
+
+impl fmt::Display for KeyringError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            KeyringError::DuplicateKey(id) => write!(f, "duplicate key id {}", id),
+            KeyringError::UnknownKey(id) => write!(f, "unknown key id {}", id),
+            KeyringError::Expired(id) => write!(f, "key {} has expired", id),
+            KeyringError::NoActiveKey => write!(f, "no active key"),
+            KeyringError::Authentication => write!(f, "tag verification failed")
+        }
+    }
+}
+
+impl Error for KeyringError { }
+
+
+#[cfg(test)]
+mod tests {
+    use clock::ManualClock;
+    use core::*;
+    use keyfile::KeyFile;
+    use quickcheck::quickcheck;
+    use std::convert::TryFrom;
+    use super::{KeyState, KeyedTag, Keyring, KeyringError};
+    use {Digester, ParseTagError, Tag};
+
+    const KEY: [u32; 4] = [0x833D3433, 0x009F389F, 0x2398E64F, 0x417ACF39];
+
+    /// A keyring with keys 1 to 3, one of each variant, and 2 active.
+    fn keyring(clock: &ManualClock) -> Keyring<&ManualClock> {
+        let mut keyring = Keyring::with_clock(clock);
+        keyring.insert(1, Variant::Chaskey, [1, 1, 1, 1]).unwrap();
+        keyring.insert(2, Variant::Chaskey12, [2, 2, 2, 2]).unwrap();
+        keyring.insert(3, Variant::ChaskeyLTS, [3, 3, 3, 3]).unwrap();
+        keyring.activate(2).unwrap();
+        keyring
+    }
+
+    #[test]
+    fn variants() {
+        fn tag<P: Permutation>(key: [u32; 4]) -> Tag {
+            let mut digester: Digester<P> = Digester::new(key);
+            digester.write(b"msg");
+            digester.finish()
+        }
+        let clock = ManualClock::new(0);
+        let mut keyring = keyring(&clock);
+        assert_eq!(keyring.sign(b"msg").unwrap(), KeyedTag::new(2, tag::<Chaskey12>([2; 4])));
+        keyring.activate(1).unwrap();
+        assert_eq!(keyring.sign(b"msg").unwrap(), KeyedTag::new(1, tag::<Chaskey>([1; 4])));
+        keyring.activate(3).unwrap();
+        assert_eq!(keyring.sign(b"msg").unwrap(), KeyedTag::new(3, tag::<ChaskeyLTS>([3; 4])));
+
+        let file = KeyFile::new(Variant::Chaskey12, KEY, None).unwrap();
+        keyring.insert_key_file(4, &file).unwrap();
+        let tag = KeyedTag::new(4, tag::<Chaskey12>(KEY));
+        assert_eq!(keyring.verify(b"msg", &tag), Ok(()));
+    }
+
+    #[test]
+    fn rotation() {
+        let clock = ManualClock::new(1000);
+        let mut keyring = keyring(&clock);
+        let old = keyring.sign(b"msg").unwrap();
+
+        keyring.rotate(4, Variant::Chaskey, KEY, 100).unwrap();
+        assert_eq!(keyring.active_id(), Some(4));
+        assert_eq!(keyring.state(2), Some(KeyState::ValidUntil(1100)));
+        assert_eq!(keyring.state(4), Some(KeyState::Active));
+        let new = keyring.sign(b"msg").unwrap();
+        assert_eq!(new.key_id(), 4);
+
+        // Both keys verify during the grace period...
+        clock.advance(99);
+        assert_eq!(keyring.verify(b"msg", &old), Ok(()));
+        assert_eq!(keyring.verify_any(b"msg", &old.tag()), Ok(2));
+        assert_eq!(keyring.verify_any(b"msg", &new.tag()), Ok(4));
+
+        // ...and only the new one afterwards.
+        clock.advance(1);
+        assert_eq!(keyring.verify(b"msg", &old), Err(KeyringError::Expired(2)));
+        assert_eq!(keyring.verify_any(b"msg", &old.tag()), Err(KeyringError::Authentication));
+        assert_eq!(keyring.verify(b"msg", &new), Ok(()));
+        assert_eq!(keyring.activate(2), Err(KeyringError::Expired(2)));
+
+        assert_eq!(keyring.ids(), [1, 2, 3, 4]);
+        keyring.prune();
+        assert_eq!(keyring.ids(), [1, 3, 4]);
+        assert_eq!(keyring.verify(b"msg", &old), Err(KeyringError::UnknownKey(2)));
+    }
+
+    #[test]
+    fn verify_any() {
+        fn prop(msg: Vec<u8>, signer: u8) -> bool {
+            let clock = ManualClock::new(0);
+            let mut keyring = keyring(&clock);
+            let signer = 1 + signer % 3;
+            keyring.activate(signer).unwrap();
+            let tag = keyring.sign(&msg).unwrap().tag();
+            let mut forged = tag.raw_bytes();
+            forged[0] ^= 1;
+            keyring.verify_any(&msg, &tag) == Ok(signer)
+                && keyring.verify_any(&msg, &Tag::from(forged)) == Err(KeyringError::Authentication)
+        }
+        quickcheck(prop as fn(Vec<u8>, u8) -> bool);
+    }
+
+    #[test]
+    fn errors() {
+        let clock = ManualClock::new(0);
+        let mut keyring = keyring(&clock);
+        assert_eq!(keyring.insert(1, Variant::Chaskey, KEY), Err(KeyringError::DuplicateKey(1)));
+        assert_eq!(keyring.rotate(3, Variant::Chaskey, KEY, 0), Err(KeyringError::DuplicateKey(3)));
+        assert_eq!(keyring.activate(9), Err(KeyringError::UnknownKey(9)));
+        assert_eq!(keyring.retire(9, 0), Err(KeyringError::UnknownKey(9)));
+
+        let tag = keyring.sign(b"msg").unwrap();
+        assert_eq!(keyring.verify(b"other", &tag), Err(KeyringError::Authentication));
+        assert_eq!(keyring.verify(b"msg", &KeyedTag::new(1, tag.tag())),
+                   Err(KeyringError::Authentication));
+        assert_eq!(keyring.verify(b"msg", &KeyedTag::new(9, tag.tag())),
+                   Err(KeyringError::UnknownKey(9)));
+
+        keyring.retire(2, 10).unwrap();
+        assert_eq!(keyring.sign(b"msg"), Err(KeyringError::NoActiveKey));
+        assert_eq!(keyring.verify(b"msg", &tag), Ok(()));
+        keyring.activate(1).unwrap();
+        assert!(keyring.remove(1));
+        assert!(!keyring.remove(1));
+        assert_eq!(keyring.sign(b"msg"), Err(KeyringError::NoActiveKey));
+    }
+
+    #[test]
+    fn keyed_tag_conversions() {
+        let tag = KeyedTag::new(0xab, Tag::new([0x03020100, 0x07060504, 0x0b0a0908, 0x0f0e0d0c]));
+        let hex = "ab000102030405060708090a0b0c0d0e0f";
+        assert_eq!(tag.to_string(), hex);
+        assert_eq!(hex.parse(), Ok(tag));
+        assert_eq!(hex.to_uppercase().parse(), Ok(tag));
+        assert_eq!(KeyedTag::try_from(&tag.to_bytes()[..]), Ok(tag));
+        assert_eq!(tag.to_bytes()[0], 0xab);
+
+        assert_eq!("ab00".parse::<KeyedTag>(), Err(ParseTagError::InvalidLength(4)));
+        assert_eq!(hex[2..].parse::<KeyedTag>(), Err(ParseTagError::InvalidLength(32)));
+        assert_eq!("xb000102030405060708090a0b0c0d0e0f".parse::<KeyedTag>(),
+                   Err(ParseTagError::InvalidCharacter('x', 0)));
+        assert_eq!(KeyedTag::try_from(&[0u8; 16][..]), Err(ParseTagError::InvalidLength(16)));
+    }
+}
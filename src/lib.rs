@@ -19,6 +19,30 @@
 //! Variants are selected by the type parameter given to the
 //! `Digester` type.  
 //!
+//! When you have several messages to authenticate at once, a
+//! `MultiDigester` will process them with interleaved rounds, which
+//! is usually faster than running one `Digester` after another.
+//!
+//! To use one key for several purposes, `Digester::with_personalization`
+//! derives a digester for each context string, and tags from different
+//! contexts are as unrelated as tags under different keys.
+//!
+//! Tags, truncated tags and key files implement the constant-time
+//! traits of the [`subtle`](https://docs.rs/subtle) crate, which this
+//! crate re-exports, so they can be combined with other constant-time
+//! checks.  (Raw `[u32; 4]` keys can be compared with `subtle`'s slice
+//! implementation, `a[..].ct_eq(&b[..])`, and selected with its array
+//! one, `<[u32; 4]>::conditional_select(&a, &b, choice)`.)
+//!
+//! With the `ffi` feature, the crate also exports a C interface to
+//! the MAC and the block cipher, declared in `include/chaskey.h`;
+//! the `chaskey-ffi` crate in the repository builds it as C libraries.
+//!
+//! With the `serde` feature, tags, truncated tags, variants and key
+//! files implement `Serialize` and `Deserialize`.  Tags are hex
+//! strings in human-readable formats such as JSON, and raw bytes in
+//! binary formats such as CBOR.
+//!
 //! ## Disclaimer
 //! 
 //! While Chaskey is a cryptographic algorithm, **this implementation
@@ -27,9 +51,18 @@
 //! ## Correctness
 //!
 //! The 8-round and 12-round MACs variants are tested with the
-//! official test vectors.  I have not found test vectors for the
-//! Chaskey-LTS MAC, but the FELICS project has some for its
-//! underlying **block cipher**, which I do verify.
+//! official test vectors.  I have not found official test vectors
+//! for the Chaskey-LTS MAC, so its 64 incremental vectors (same key
+//! and messages as the official ones) come from a port of the
+//! reference C code to a configurable round count, kept in
+//! `tests/reference`; that port reproduces the official 8- and
+//! 12-round vectors.  The FELICS project also has test vectors for
+//! the underlying Chaskey-LTS **block cipher**, which I do verify.
+//!
+//! The `tests/kat` directory has known-answer files for the MAC and
+//! the block cipher of every variant, in the format of the [`kat`]
+//! module, for cross-checking other implementations.  They are
+//! regenerated with `cargo run --example katgen`.
 //!
 //! ## Examples
 //!
@@ -38,10 +71,8 @@
 //! ```
 //! # extern crate chaskey;
 //! # extern crate rand;
-//! # extern crate rustc_serialize;
 //! use chaskey::{Digester, Chaskey, Tag};
 //! use rand::{Rng, OsRng};
-//! use rustc_serialize::hex::ToHex;
 //! 
 //! # fn main() {
 //! let mut rng: OsRng = OsRng::new().unwrap();
@@ -52,21 +83,21 @@
 //!     mac.write("Hello world!".as_bytes());
 //!     mac.finish()
 //! };
-//! println!("tag1 = {}", tag1.to_hex());
+//! println!("tag1 = {}", tag1);
 //!
 //! let tag2: Tag = {
 //!     mac.reset();
 //!     mac.write("Hello world!".as_bytes());
 //!     mac.finish()
 //! };
-//! println!("tag2 = {}", tag2.to_hex());
+//! println!("tag2 = {}", tag2);
 //!
 //! let tag3: Tag = {
 //!     mac.reset();
 //!     mac.write("mwahahahaha!".as_bytes());
 //!     mac.finish()
 //! };
-//! println!("tag3 = {}", tag3.to_hex());
+//! println!("tag3 = {}", tag3);
 //!
 //! // Note that the `Tag` type does constant-time equality
 //! // comparisons (or at least it tries to), so as to avoid
@@ -82,10 +113,8 @@
 //! ```
 //! # extern crate chaskey;
 //! # extern crate rand;
-//! # extern crate rustc_serialize;
 //! use chaskey::{Digester, Chaskey, Chaskey12, ChaskeyLTS, Tag};
 //! use rand::{Rng, OsRng};
-//! use rustc_serialize::hex::ToHex;
 //! 
 //! # fn main() {
 //! let mut rng: OsRng = OsRng::new().unwrap();
@@ -97,7 +126,7 @@
 //!     mac.write("Hello world!".as_bytes());
 //!     mac.finish()
 //! };
-//! println!("tag1 = {}", tag1.to_hex());
+//! println!("tag1 = {}", tag1);
 //!
 //! let tag2: Tag = {
 //!     // Note the `Chaskey12` type parameter here:
@@ -105,7 +134,7 @@
 //!     mac.write("Hello world!".as_bytes());
 //!     mac.finish()
 //! };
-//! println!("tag2 = {}", tag2.to_hex());
+//! println!("tag2 = {}", tag2);
 //!
 //! let tag3: Tag = {
 //!     // Note the `ChaskeyLTS` type parameter here:
@@ -113,7 +142,7 @@
 //!     mac.write("Hello world!".as_bytes());
 //!     mac.finish()
 //! };
-//! println!("tag3 = {}", tag3.to_hex());
+//! println!("tag3 = {}", tag3);
 //! # assert!(tag1 != tag2);
 //! # assert!(tag1 != tag3);
 //! # assert!(tag2 != tag3);
@@ -139,32 +168,64 @@
 //!   accessed May 2016.
 
 extern crate byteorder;
-extern crate rustc_serialize as serialize;
+extern crate rand;
+pub extern crate subtle;
+extern crate zeroize;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(all(test, feature = "serde"))]
+extern crate serde_test;
 #[cfg(test)]
 extern crate quickcheck;
 
+mod base64;
+pub mod challenge;
 pub mod cipher;
+pub mod clock;
 pub mod core;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod frame;
+pub mod hex;
+pub mod kat;
+pub mod keyfile;
+pub mod keyring;
+pub mod multi;
+pub mod otp;
+pub mod secoc;
+pub mod structured;
+pub mod token;
+#[cfg(feature = "serde")]
+mod serde_impls;
 mod util;
 
 use byteorder::{ByteOrder, LittleEndian};
-pub use core::{Chaskey, Chaskey12, ChaskeyLTS};
+pub use core::{Chaskey, Chaskey12, ChaskeyLTS, Variant};
+pub use multi::MultiDigester;
 use core::{times_two, Permutation};
-use serialize::hex::ToHex;
-use std::hash::Hasher;
+use hex::{FromHex, FromHexError, ToHex};
+use rand::Rng;
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
 use std::marker::PhantomData;
-use util::{xor_u32x4, xor_u8x16};
+use std::mem;
+use std::str::FromStr;
+use subtle::{Choice, ConditionallySelectable, ConstantTimeEq};
+use util::{read_u32x4, write_u32x4, xor_u32x4};
+use zeroize::Zeroize;
 
 
 
 /// A 128-bit Chaskey tag.
 ///
 /// **WARNING:** This is a wrapper around an `[u32; 4]` in order to
-/// provide constant-time equality comparisons.  You may unwrap it if
-/// you like, but you need to be very careful what you do with it, or
-/// otherwise you may risk a [timing
-/// attack](https://en.wikipedia.org/wiki/Timing_attack)!
-#[derive(Debug)]
+/// provide constant-time equality comparisons (both `==` and
+/// `subtle::ConstantTimeEq`).  You may unwrap it if you like, but you
+/// need to be very careful what you do with it, or otherwise you may
+/// risk a [timing attack](https://en.wikipedia.org/wiki/Timing_attack)!
+#[derive(Clone, Copy, Debug)]
 pub struct Tag([u32; 4]);
 
 impl Tag {
@@ -183,23 +244,45 @@ impl Tag {
     }
 
     pub fn raw_bytes(&self) -> [u8; 16] {
-        let mut result = [0u8; 16];
-        LittleEndian::write_u32(&mut result[0..4], self.0[0]);
-        LittleEndian::write_u32(&mut result[4..8], self.0[1]);
-        LittleEndian::write_u32(&mut result[8..12], self.0[2]);
-        LittleEndian::write_u32(&mut result[12..16], self.0[3]);
-        result
+        write_u32x4(&self.0)
+    }
+
+    /// Truncate the tag to its first `len` bytes.
+    ///
+    /// # Panics
+    ///
+    /// Unless `len` is between 1 and 16.
+    pub fn truncate(&self, len: usize) -> TruncatedTag {
+        assert!((1..=16).contains(&len), "invalid truncated tag length {}", len);
+        let mut bytes = self.raw_bytes();
+        for byte in bytes[len..].iter_mut() {
+            *byte = 0;
+        }
+        TruncatedTag { bytes, len: len as u8 }
+    }
+}
+
+impl ConstantTimeEq for Tag {
+    fn ct_eq(&self, other: &Tag) -> Choice {
+        self.0[..].ct_eq(&other.0[..])
+    }
+}
+
+impl ConditionallySelectable for Tag {
+    fn conditional_select(a: &Tag, b: &Tag, choice: Choice) -> Tag {
+        let mut words = [0u32; 4];
+        for (i, word) in words.iter_mut().enumerate() {
+            *word = u32::conditional_select(&a.0[i], &b.0[i], choice);
+        }
+        Tag(words)
     }
 }
 
 impl PartialEq for Tag {
+    /// Constant-time comparison, through `ConstantTimeEq`.  The
+    /// `constant_time` test in `tests/` checks this statistically.
     fn eq(&self, other: &Tag) -> bool {
-        let mut result = true;
-        result &= self.0[0] == other.0[0];
-        result &= self.0[1] == other.0[1];
-        result &= self.0[2] == other.0[2];
-        result &= self.0[3] == other.0[3];
-        result
+        self.ct_eq(other).into()
     }
 }
 
@@ -211,23 +294,223 @@ impl ToHex for Tag {
     }
 }
 
+/// Lowercase hex of `raw_bytes`, the same as `to_hex`.
+impl fmt::Display for Tag {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::LowerHex::fmt(self, f)
+    }
+}
+
+impl fmt::LowerHex for Tag {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad(&self.to_hex())
+    }
+}
+
+/// Parse 32 hex digits, in the order of `raw_bytes`.
+impl FromStr for Tag {
+    type Err = ParseTagError;
+
+    fn from_str(s: &str) -> Result<Tag, ParseTagError> {
+        parse_hex_tag(s, 16, 16).map(|(bytes, _)| Tag(read_u32x4(&bytes)))
+    }
+}
+
+/// Read the 16 bytes of `raw_bytes`.
+impl TryFrom<&[u8]> for Tag {
+    type Error = ParseTagError;
+
+    fn try_from(bytes: &[u8]) -> Result<Tag, ParseTagError> {
+        if bytes.len() != 16 {
+            return Err(ParseTagError::InvalidLength(bytes.len()));
+        }
+        Ok(Tag(read_u32x4(bytes)))
+    }
+}
+
+/// The inverse of `raw_bytes`.
+impl From<[u8; 16]> for Tag {
+    fn from(bytes: [u8; 16]) -> Tag {
+        Tag(read_u32x4(&bytes))
+    }
+}
+
+impl From<Tag> for [u8; 16] {
+    fn from(tag: Tag) -> [u8; 16] {
+        tag.raw_bytes()
+    }
+}
+
+
+/// A Chaskey tag truncated to its first 1 to 16 bytes, for protocols
+/// that send shorter tags.  Made with `Tag::truncate`, or from
+/// received bytes with `TruncatedTag::new`.
+///
+/// Comparisons are constant-time in the tag bytes, but not in their
+/// length: truncated tags of different lengths are never equal, and
+/// the lengths are not treated as secret.
+#[derive(Clone, Copy, Debug)]
+pub struct TruncatedTag {
+    // Bytes past `len` are always zero.
+    bytes: [u8; 16],
+    len: u8
+}
+
+impl TruncatedTag {
+    /// Wrap the bytes of a truncated tag.  Returns `None` unless
+    /// there are 1 to 16 of them.
+    pub fn new(tag: &[u8]) -> Option<TruncatedTag> {
+        if tag.is_empty() || tag.len() > 16 {
+            return None;
+        }
+        let mut bytes = [0u8; 16];
+        bytes[..tag.len()].copy_from_slice(tag);
+        Some(TruncatedTag { bytes, len: tag.len() as u8 })
+    }
+
+    /// The tag's bytes.  The same timing warning as for
+    /// `Tag::raw_words` applies.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes[..self.len as usize]
+    }
+}
+
+impl ConstantTimeEq for TruncatedTag {
+    fn ct_eq(&self, other: &TruncatedTag) -> Choice {
+        self.as_bytes().ct_eq(other.as_bytes())
+    }
+}
+
+impl ConditionallySelectable for TruncatedTag {
+    fn conditional_select(a: &TruncatedTag, b: &TruncatedTag, choice: Choice) -> TruncatedTag {
+        let mut bytes = [0u8; 16];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = u8::conditional_select(&a.bytes[i], &b.bytes[i], choice);
+        }
+        TruncatedTag { bytes, len: u8::conditional_select(&a.len, &b.len, choice) }
+    }
+}
+
+impl PartialEq for TruncatedTag {
+    /// Constant-time comparison, through `ConstantTimeEq`.  The
+    /// `constant_time` test in `tests/` checks this statistically.
+    fn eq(&self, other: &TruncatedTag) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl Eq for TruncatedTag { }
+
+impl ToHex for TruncatedTag {
+    fn to_hex(&self) -> String {
+        self.as_bytes().to_hex()
+    }
+}
+
+impl fmt::Display for TruncatedTag {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::LowerHex::fmt(self, f)
+    }
+}
+
+impl fmt::LowerHex for TruncatedTag {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad(&self.to_hex())
+    }
+}
+
+/// Parse 2 to 32 hex digits.
+impl FromStr for TruncatedTag {
+    type Err = ParseTagError;
 
-/// A Chaskey key schedule.
-#[derive(Clone, Copy)]
+    fn from_str(s: &str) -> Result<TruncatedTag, ParseTagError> {
+        parse_hex_tag(s, 1, 16).map(|(bytes, len)| TruncatedTag { bytes, len: len as u8 })
+    }
+}
+
+impl TryFrom<&[u8]> for TruncatedTag {
+    type Error = ParseTagError;
+
+    fn try_from(bytes: &[u8]) -> Result<TruncatedTag, ParseTagError> {
+        TruncatedTag::new(bytes).ok_or(ParseTagError::InvalidLength(bytes.len()))
+    }
+}
+
+impl AsRef<[u8]> for TruncatedTag {
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+/// Decode a hex tag of `min` to `max` bytes.
+fn parse_hex_tag(s: &str, min: usize, max: usize) -> Result<([u8; 16], usize), ParseTagError> {
+    let bytes = decode_hex_tag(s)?;
+    if bytes.len() < min || bytes.len() > max {
+        return Err(ParseTagError::InvalidLength(s.len()));
+    }
+    let mut result = [0u8; 16];
+    result[..bytes.len()].copy_from_slice(&bytes);
+    Ok((result, bytes.len()))
+}
+
+/// Decode hex, with errors as for parsing a tag.
+fn decode_hex_tag(s: &str) -> Result<Vec<u8>, ParseTagError> {
+    s.from_hex().map_err(|e| match e {
+        FromHexError::InvalidHexCharacter(c, i) => ParseTagError::InvalidCharacter(c, i),
+        FromHexError::InvalidHexLength => ParseTagError::InvalidLength(s.len())
+    })
+}
+
+/// Errors from parsing a `Tag` or `TruncatedTag` from hex or bytes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParseTagError {
+    /// The input has the wrong length: the number of bytes given,
+    /// or of characters when parsing hex.
+    InvalidLength(usize),
+    /// The character at the given byte offset is not a hex digit.
+    InvalidCharacter(char, usize)
+}
+
+impl fmt::Display for ParseTagError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ParseTagError::InvalidLength(len) =>
+                write!(f, "invalid tag length {}", len),
+            ParseTagError::InvalidCharacter(c, i) =>
+                write!(f, "invalid hex character {:?} in tag at offset {}", c, i)
+        }
+    }
+}
+
+impl Error for ParseTagError { }
+
+
+/// A Chaskey key schedule.  It is wiped when dropped, and so are
+/// the `Digester`s, `MultiDigester`s and `ChaskeyBuildHasher`s that
+/// hold one.
+#[derive(Clone)]
 struct Keys {
     key: [u32; 4],
     k1: [u32; 4],
     k2: [u32; 4]
 }
 
+impl Drop for Keys {
+    fn drop(&mut self) {
+        self.key.zeroize();
+        self.k1.zeroize();
+        self.k2.zeroize();
+    }
+}
+
 /// Construct the Chaskey key schedule from the given key.
 fn make_keys(key: [u32; 4]) -> Keys {
     let k1: [u32; 4] = times_two(&key);
     let k2: [u32; 4] = times_two(&k1);
     Keys {
-        key: key,
-        k1: k1,
-        k2: k2
+        key,
+        k1,
+        k2
     }
 }
 
@@ -236,11 +519,14 @@ fn make_keys(key: [u32; 4]) -> Keys {
 /// interact with it as you would do with one of them.  Additionally
 /// you may use `Digester`'s own `finish` method to get a full 128-bit
 /// `Tag`.
+///
+/// Input is XORed into the state as soon as it is written; only the
+/// permutation is deferred until we know whether the current block
+/// is the last one.
 pub struct Digester<P> {
     permutation: PhantomData<P>,
       keys: Keys,
      state: [u32; 4],
-       buf: [u8; 16],
          i: usize
 }
 
@@ -248,52 +534,107 @@ impl<P: Permutation> Digester<P> {
     /// Initialize a new Digester (8 rounds) digester with the given
     /// key.
     pub fn new(key: [u32; 4]) -> Digester<P> {
-        let keys = make_keys(key);
+        Digester::from_keys(make_keys(key))
+    }
+
+    /// Initialize a digester whose tags are specific to `context`,
+    /// similar to BLAKE2's personalization.  Digesters made from one
+    /// key with different contexts, or with `new`, behave as if they
+    /// had independent keys, so their tags are distinct even for the
+    /// same messages.
+    ///
+    /// The digester's key is derived from `key` and `context` by
+    /// authenticating `context` with the usual Chaskey procedure,
+    /// except that the padding (`0x01`, then zeros to the end of the
+    /// block) is always added, and the last block is whitened with a
+    /// third subkey `K3 = 2·K2` instead of `K1` or `K2`.  So a derived
+    /// key is never the same computation as a tag made under `key`.
+    /// The derived key is the result, read as four little-endian
+    /// words like a tag's `raw_words`.
+    ///
+    /// ```
+    /// # extern crate chaskey;
+    /// use chaskey::{Chaskey, Digester};
+    ///
+    /// # fn main() {
+    /// let key = [1, 2, 3, 4];
+    /// let mut audit: Digester<Chaskey> = Digester::with_personalization(key, b"audit-log");
+    /// let mut session: Digester<Chaskey> = Digester::with_personalization(key, b"session");
+    /// audit.write(b"message");
+    /// session.write(b"message");
+    /// assert!(audit.finish() != session.finish());
+    /// # }
+    /// ```
+    pub fn with_personalization(key: [u32; 4], context: &[u8]) -> Digester<P> {
+        let mut derived: Digester<P> = Digester::new(key);
+        derived.write(context);
+        derived.write(&[0x01]);
+        let mut k3 = times_two(&derived.keys.k2);
+        xor_u32x4(&mut derived.state, &k3);
+        P::permute(&mut derived.state);
+        xor_u32x4(&mut derived.state, &k3);
+        k3.zeroize();
+        Digester::new(derived.state)
+    }
+
+    /// Initialize a new digester from an already computed key
+    /// schedule.
+    fn from_keys(keys: Keys) -> Digester<P> {
         Digester {
             permutation: PhantomData,
-              keys: keys,
-             state: key,
-               buf: [0u8; 16],
+             state: keys.key,
+              keys,
                  i: 0
         }
     }
 
     /// Reset the digester to its initial state, so it is ready to
     /// authenticate a new message with the same key as when
-    /// initialized.
+    /// initialized.  The old state is wiped first.
     pub fn reset(&mut self) {
+        self.state.zeroize();
         self.state = self.keys.key;
-        self.buf = [0u8; 16];
         self.i = 0;
     }
 
     /// Write data to the digester.
     pub fn write(&mut self, bytes: &[u8]) {
         for byte in bytes.iter() {
-            if self.i % 16 == 0 && self.i != 0 {
-                xor_u8x16(&mut self.state, &self.buf);
+            if self.i.is_multiple_of(16) && self.i != 0 {
                 P::permute(&mut self.state);
             }
-            self.buf[self.i % 16] = *byte;
+            self.state[(self.i % 16) / 4] ^= (*byte as u32) << (8 * (self.i % 4));
             self.i += 1;
         }
     }
+
+    /// Write four bytes, given as a little-endian word, to the
+    /// digester.  When the input so far is a whole number of words
+    /// this goes straight into the state instead of byte by byte.
+    #[inline]
+    fn write_word(&mut self, word: u32) {
+        if self.i.is_multiple_of(4) {
+            if self.i.is_multiple_of(16) && self.i != 0 {
+                P::permute(&mut self.state);
+            }
+            self.state[(self.i % 16) / 4] ^= word;
+            self.i += 4;
+        } else {
+            let mut bytes = [0u8; 4];
+            LittleEndian::write_u32(&mut bytes, word);
+            self.write(&bytes);
+        }
+    }
     
     pub fn finish(&self) -> Tag {
         let mut result = self.state;
         let buflen = self.i % 16;
         if buflen == 0 && self.i != 0 {
-            xor_u8x16(&mut result, &self.buf);
             xor_u32x4(&mut result, &self.keys.k1);
             P::permute(&mut result);
             xor_u32x4(&mut result, &self.keys.k1);
         } else {
-            let mut last = [0u8; 16];
-            for i in 0..buflen {
-                last[i] = self.buf[i];
-            }
-            last[buflen] = 0x01;
-            xor_u8x16(&mut result, &last);
+            result[buflen / 4] ^= 0x01 << (8 * (buflen % 4));
             xor_u32x4(&mut result, &self.keys.k2);
             P::permute(&mut result);
             xor_u32x4(&mut result, &self.keys.k2);
@@ -303,21 +644,126 @@ impl<P: Permutation> Digester<P> {
 
 }
 
+/// Wipe the state; the key schedule wipes itself.
+impl<P> Drop for Digester<P> {
+    fn drop(&mut self) {
+        self.state.zeroize();
+        self.i.zeroize();
+    }
+}
+
+/// The integer methods feed the digester the same bytes as the
+/// default implementations do (the integer's native-endian
+/// representation), but a word at a time.
 impl<P: Permutation> Hasher for Digester<P> {
     fn write(&mut self, bytes: &[u8]) {
         Digester::write(self, bytes);
     }
 
+    #[inline]
+    fn write_u32(&mut self, n: u32) {
+        self.write_word(n.to_le());
+    }
+
+    #[inline]
+    fn write_u64(&mut self, n: u64) {
+        let bytes = n.to_ne_bytes();
+        self.write_word(LittleEndian::read_u32(&bytes[0..4]));
+        self.write_word(LittleEndian::read_u32(&bytes[4..8]));
+    }
+
+    #[inline]
+    fn write_usize(&mut self, n: usize) {
+        match mem::size_of::<usize>() {
+            4 => self.write_u32(n as u32),
+            8 => self.write_u64(n as u64),
+            _ => Digester::write(self, &n.to_ne_bytes())
+        }
+    }
+
     fn finish(&self) -> u64 {
         self.finish().to_u64()
     }
 }
 
 
+/// A `BuildHasher` that creates `Digester`s, so that Chaskey can be
+/// used as the hash function of a `HashMap` or `HashSet`:
+///
+/// ```
+/// # extern crate chaskey;
+/// use chaskey::ChaskeyBuildHasher;
+/// use std::collections::HashMap;
+///
+/// # fn main() {
+/// let mut map: HashMap<&str, u32, ChaskeyBuildHasher> =
+///     HashMap::with_hasher(ChaskeyBuildHasher::new());
+/// map.insert("one", 1);
+/// assert_eq!(map.get("one"), Some(&1));
+/// # }
+/// ```
+///
+/// The type parameter selects the Chaskey variant, and defaults to
+/// the original 8-round one.
+pub struct ChaskeyBuildHasher<P = Chaskey> {
+    permutation: PhantomData<P>,
+    keys: Keys
+}
+
+impl<P: Permutation> ChaskeyBuildHasher<P> {
+    /// Create a `ChaskeyBuildHasher` with a random key.  Like
+    /// `RandomState`, each instance gets a different key, so hash
+    /// values (and iteration orders) are not reproducible.
+    pub fn new() -> ChaskeyBuildHasher<P> {
+        let mut rng = rand::thread_rng();
+        ChaskeyBuildHasher::with_key(rng.gen())
+    }
+
+    /// Create a `ChaskeyBuildHasher` with a fixed key, for
+    /// reproducible hash values.
+    pub fn with_key(key: [u32; 4]) -> ChaskeyBuildHasher<P> {
+        ChaskeyBuildHasher {
+            permutation: PhantomData,
+            keys: make_keys(key)
+        }
+    }
+}
+
+impl<P: Permutation> BuildHasher for ChaskeyBuildHasher<P> {
+    type Hasher = Digester<P>;
+
+    fn build_hasher(&self) -> Digester<P> {
+        Digester::from_keys(self.keys.clone())
+    }
+}
+
+impl<P: Permutation> Default for ChaskeyBuildHasher<P> {
+    fn default() -> ChaskeyBuildHasher<P> {
+        ChaskeyBuildHasher::new()
+    }
+}
+
+impl<P> Clone for ChaskeyBuildHasher<P> {
+    fn clone(&self) -> ChaskeyBuildHasher<P> {
+        ChaskeyBuildHasher {
+            permutation: PhantomData,
+            keys: self.keys.clone()
+        }
+    }
+}
+
+
 #[cfg(test)]
 mod tests {
     use core::*;
-    use super::{Digester, Tag};
+    use super::{ChaskeyBuildHasher, Digester, MultiDigester, ParseTagError, Tag, TruncatedTag};
+    use quickcheck::{Arbitrary, Gen, quickcheck};
+    use std::collections::HashMap;
+    use std::convert::TryFrom;
+    use std::hash::{BuildHasher, Hasher};
+    use std::mem::{self, ManuallyDrop};
+    use std::ptr;
+    use subtle::{Choice, ConditionallySelectable, ConstantTimeEq};
 
     #[test]
     fn test_incremental_8() {
@@ -329,6 +775,11 @@ mod tests {
         test_incremental::<Chaskey12>(&TEST_VECTORS_12);
     }
 
+    #[test]
+    fn test_incremental_lts() {
+        test_incremental::<ChaskeyLTS>(&TEST_VECTORS_LTS);
+    }
+
     fn test_incremental<P: Permutation>(expected: &[Tag; 64]) {
         let mut hasher: Digester<P> = Digester::new(KEY);
         let mut message: [u8; 64] = [0u8; 64];
@@ -343,6 +794,339 @@ mod tests {
         }
     }
 
+    #[test]
+    fn personalization_8() {
+        personalization::<Chaskey>(&PERSONALIZATION_VECTORS_8);
+    }
+
+    #[test]
+    fn personalization_12() {
+        personalization::<Chaskey12>(&PERSONALIZATION_VECTORS_12);
+    }
+
+    #[test]
+    fn personalization_lts() {
+        personalization::<ChaskeyLTS>(&PERSONALIZATION_VECTORS_LTS);
+    }
+
+    fn personalization<P: Permutation>(expected: &[Tag; 4]) {
+        let message: Vec<u8> = (0..16).collect();
+        let mut tags = Vec::new();
+        for (i, context) in PERSONALIZATION_CONTEXTS.iter().enumerate() {
+            let mut digester: Digester<P> = Digester::with_personalization(KEY, context);
+            digester.write(&message);
+            let tag = digester.finish();
+            assert_eq!(tag, expected[i]);
+            tags.push(tag);
+        }
+        let mut plain: Digester<P> = Digester::new(KEY);
+        plain.write(&message);
+        tags.push(plain.finish());
+        for (i, a) in tags.iter().enumerate() {
+            assert!(tags[i + 1..].iter().all(|b| a != b));
+        }
+    }
+
+    #[test]
+    fn tag_constant_time_traits() {
+        let a = Tag::new([1, 2, 3, 4]);
+        let b = Tag::new([1, 2, 3, 5]);
+        assert!(bool::from(a.ct_eq(&a)));
+        assert!(!bool::from(a.ct_eq(&b)));
+        assert_eq!(Tag::conditional_select(&a, &b, Choice::from(0)), a);
+        assert_eq!(Tag::conditional_select(&a, &b, Choice::from(1)), b);
+
+        // Raw keys get the traits from `subtle`.
+        let (k, l) = ([1u32, 2, 3, 4], [5u32, 6, 7, 8]);
+        assert!(bool::from(k[..].ct_eq(&k[..])));
+        assert!(!bool::from(k[..].ct_eq(&l[..])));
+        assert_eq!(<[u32; 4]>::conditional_select(&k, &l, Choice::from(0)), k);
+        assert_eq!(<[u32; 4]>::conditional_select(&k, &l, Choice::from(1)), l);
+    }
+
+    #[test]
+    fn truncated_tags() {
+        let tag = TEST_VECTORS_8[3];
+        let short = tag.truncate(8);
+        assert_eq!(short.as_bytes(), &tag.raw_bytes()[..8]);
+        assert_eq!(TruncatedTag::new(&tag.raw_bytes()[..8]), Some(short));
+        assert!(TruncatedTag::new(&[]).is_none());
+        assert!(TruncatedTag::new(&[0; 17]).is_none());
+
+        // Different lengths never match, even if one is a prefix of
+        // the other.
+        assert!(tag.truncate(4) != short);
+        assert!(TEST_VECTORS_8[4].truncate(8) != short);
+
+        let other = TEST_VECTORS_8[4].truncate(4);
+        assert_eq!(TruncatedTag::conditional_select(&short, &other, Choice::from(0)), short);
+        assert_eq!(TruncatedTag::conditional_select(&short, &other, Choice::from(1)), other);
+    }
+
+    #[test]
+    fn tag_conversions() {
+        // The 8-round tag of `00 01 02`.
+        let tag = TEST_VECTORS_8[3];
+        let hex = "64b2db1bd88076a0b82a5b8e13046620";
+        assert_eq!(tag.to_string(), hex);
+        assert_eq!(format!("{:x}", tag), hex);
+        assert_eq!(hex.parse::<Tag>(), Ok(tag));
+        assert_eq!(hex.to_uppercase().parse::<Tag>(), Ok(tag));
+        assert_eq!(Tag::from(tag.raw_bytes()), tag);
+        assert_eq!(<[u8; 16]>::from(tag), tag.raw_bytes());
+        assert_eq!(Tag::try_from(&tag.raw_bytes()[..]), Ok(tag));
+
+        assert_eq!("64b2".parse::<Tag>(), Err(ParseTagError::InvalidLength(4)));
+        assert_eq!(hex[1..].parse::<Tag>(), Err(ParseTagError::InvalidLength(31)));
+        assert_eq!(format!("{}0", hex).parse::<Tag>(), Err(ParseTagError::InvalidLength(33)));
+        assert_eq!("x".repeat(32).parse::<Tag>(), Err(ParseTagError::InvalidCharacter('x', 0)));
+        assert_eq!(Tag::try_from(&[0u8; 15][..]), Err(ParseTagError::InvalidLength(15)));
+
+        let short = tag.truncate(4);
+        assert_eq!(short.to_string(), "64b2db1b");
+        assert_eq!("64b2db1b".parse::<TruncatedTag>(), Ok(short));
+        assert_eq!(TruncatedTag::try_from(short.as_ref()), Ok(short));
+        assert_eq!("".parse::<TruncatedTag>(), Err(ParseTagError::InvalidLength(0)));
+        assert_eq!(TruncatedTag::try_from(&[0u8; 17][..]), Err(ParseTagError::InvalidLength(17)));
+    }
+
+    #[test]
+    fn tag_round_trips() {
+        fn prop(words: (u32, u32, u32, u32)) -> bool {
+            let tag = Tag::new([words.0, words.1, words.2, words.3]);
+            tag.to_string().parse::<Tag>() == Ok(tag)
+                && Tag::try_from(&tag.raw_bytes()[..]) == Ok(tag)
+        }
+        quickcheck(prop as fn((u32, u32, u32, u32)) -> bool);
+    }
+
+    /// Drop `value` in place and return the bytes it leaves behind.
+    fn bytes_after_drop<T>(value: T) -> Vec<u8> {
+        let mut value = ManuallyDrop::new(value);
+        unsafe {
+            ManuallyDrop::drop(&mut value);
+            let ptr = &*value as *const T as *const u8;
+            (0..mem::size_of::<T>()).map(|i| ptr::read_volatile(ptr.add(i))).collect()
+        }
+    }
+
+    #[test]
+    fn zeroize_on_drop() {
+        let mut digester: Digester<Chaskey> = Digester::new(KEY);
+        digester.write(b"Hello world!");
+        assert!(bytes_after_drop(digester).iter().all(|&b| b == 0));
+
+        let builder: ChaskeyBuildHasher = ChaskeyBuildHasher::with_key(KEY);
+        assert!(bytes_after_drop(builder).iter().all(|&b| b == 0));
+
+        let multi: MultiDigester<Chaskey, 2> = MultiDigester::new([KEY, KEY]);
+        assert!(bytes_after_drop(multi).iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    fn build_hasher_fixed_key() {
+        let a: ChaskeyBuildHasher<Chaskey12> = ChaskeyBuildHasher::with_key(KEY);
+        let b: ChaskeyBuildHasher<Chaskey12> = ChaskeyBuildHasher::with_key(KEY);
+        assert_eq!(a.hash_one("Hello world!"), b.hash_one("Hello world!"));
+        assert_eq!(a.hash_one(12345u64), a.clone().hash_one(12345u64));
+
+        let mut built = a.build_hasher();
+        let mut expected: Digester<Chaskey12> = Digester::new(KEY);
+        built.write(&[1, 2, 3]);
+        expected.write(&[1, 2, 3]);
+        assert!(built.finish() == expected.finish());
+    }
+
+    #[test]
+    fn build_hasher_random_key() {
+        let a: ChaskeyBuildHasher = ChaskeyBuildHasher::new();
+        let b: ChaskeyBuildHasher = ChaskeyBuildHasher::new();
+        assert!(a.hash_one("Hello world!") != b.hash_one("Hello world!"));
+    }
+
+    #[test]
+    fn build_hasher_hash_map() {
+        let mut map: HashMap<u32, u32, ChaskeyBuildHasher> = HashMap::default();
+        for i in 0..1000 {
+            map.insert(i, i * i);
+        }
+        for i in 0..1000 {
+            assert_eq!(map.get(&i), Some(&(i * i)));
+        }
+        assert_eq!(map.get(&1000), None);
+    }
+
+    /// A call to one of the `Hasher` write methods.
+    #[derive(Clone, Debug)]
+    enum Write {
+        Bytes(Vec<u8>),
+        U32(u32),
+        U64(u64),
+        Usize(usize)
+    }
+
+    impl Arbitrary for Write {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            match g.gen_range(0, 4) {
+                0 => Write::Bytes(Arbitrary::arbitrary(g)),
+                1 => Write::U32(g.gen()),
+                2 => Write::U64(g.gen()),
+                _ => Write::Usize(g.gen())
+            }
+        }
+    }
+
+    #[test]
+    fn hasher_words_8() {
+        hasher_words::<Chaskey>();
+    }
+
+    #[test]
+    fn hasher_words_12() {
+        hasher_words::<Chaskey12>();
+    }
+
+    #[test]
+    fn hasher_words_16() {
+        hasher_words::<ChaskeyLTS>();
+    }
+
+    /// The integer `Hasher` methods must give the same results as
+    /// writing the integers' bytes.
+    fn hasher_words<P: Permutation>() {
+        fn prop<P: Permutation>(writes: Vec<Write>) -> bool {
+            let mut words: Digester<P> = Digester::new(KEY);
+            let mut bytes: Digester<P> = Digester::new(KEY);
+            for write in writes.iter() {
+                match *write {
+                    Write::Bytes(ref b) => {
+                        Hasher::write(&mut words, b);
+                        Hasher::write(&mut bytes, b);
+                    }
+                    Write::U32(n) => {
+                        words.write_u32(n);
+                        Hasher::write(&mut bytes, &n.to_ne_bytes());
+                    }
+                    Write::U64(n) => {
+                        words.write_u64(n);
+                        Hasher::write(&mut bytes, &n.to_ne_bytes());
+                    }
+                    Write::Usize(n) => {
+                        words.write_usize(n);
+                        Hasher::write(&mut bytes, &n.to_ne_bytes());
+                    }
+                }
+            }
+            words.finish() == bytes.finish()
+        }
+        quickcheck(prop::<P> as fn(Vec<Write>) -> bool);
+    }
+
+    /// A uniformly random key.
+    #[derive(Clone, Debug)]
+    struct Key([u32; 4]);
+
+    impl Arbitrary for Key {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            Key([g.gen(), g.gen(), g.gen(), g.gen()])
+        }
+    }
+
+    #[test]
+    fn chunking_8() {
+        chunking::<Chaskey>();
+    }
+
+    #[test]
+    fn chunking_12() {
+        chunking::<Chaskey12>();
+    }
+
+    #[test]
+    fn chunking_16() {
+        chunking::<ChaskeyLTS>();
+    }
+
+    /// Splitting a message across several `write` calls must give the
+    /// same tag as writing it all at once.
+    fn chunking<P: Permutation>() {
+        fn prop<P: Permutation>(key: Key, msg: Vec<u8>, chunks: Vec<usize>) -> bool {
+            let mut whole: Digester<P> = Digester::new(key.0);
+            whole.write(&msg);
+
+            let mut split: Digester<P> = Digester::new(key.0);
+            let mut rest = &msg[..];
+            for len in chunks {
+                let (chunk, tail) = rest.split_at(len % (rest.len() + 1));
+                split.write(chunk);
+                rest = tail;
+            }
+            split.write(rest);
+
+            whole.finish() == split.finish()
+        }
+        quickcheck(prop::<P> as fn(Key, Vec<u8>, Vec<usize>) -> bool);
+    }
+
+    #[test]
+    fn reset_8() {
+        reset::<Chaskey>();
+    }
+
+    #[test]
+    fn reset_12() {
+        reset::<Chaskey12>();
+    }
+
+    #[test]
+    fn reset_16() {
+        reset::<ChaskeyLTS>();
+    }
+
+    /// A `reset` digester must give the same tags as a fresh one,
+    /// whatever was written to it before.
+    fn reset<P: Permutation>() {
+        fn prop<P: Permutation>(key: Key, before: Vec<u8>, msg: Vec<u8>) -> bool {
+            let mut fresh: Digester<P> = Digester::new(key.0);
+            fresh.write(&msg);
+
+            let mut reused: Digester<P> = Digester::new(key.0);
+            reused.write(&before);
+            reused.reset();
+            reused.write(&msg);
+
+            fresh.finish() == reused.finish()
+        }
+        quickcheck(prop::<P> as fn(Key, Vec<u8>, Vec<u8>) -> bool);
+    }
+
+    #[test]
+    fn hasher_finish_8() {
+        hasher_finish::<Chaskey>();
+    }
+
+    #[test]
+    fn hasher_finish_12() {
+        hasher_finish::<Chaskey12>();
+    }
+
+    #[test]
+    fn hasher_finish_16() {
+        hasher_finish::<ChaskeyLTS>();
+    }
+
+    /// `Hasher::finish` must be the low 64 bits of the tag: its first
+    /// two words, in little-endian order.
+    fn hasher_finish<P: Permutation>() {
+        fn prop<P: Permutation>(key: Key, msg: Vec<u8>) -> bool {
+            let mut digester: Digester<P> = Digester::new(key.0);
+            digester.write(&msg);
+            let words = *digester.finish().raw_words();
+            Hasher::finish(&digester) == (words[0] as u64) | ((words[1] as u64) << 32)
+        }
+        quickcheck(prop::<P> as fn(Key, Vec<u8>) -> bool);
+    }
+
     const KEY: [u32; 4] = [0x833D3433, 0x009F389F, 0x2398E64F, 0x417ACF39];
 
     const TEST_VECTORS_8: [Tag; 64] = [
@@ -478,5 +1262,103 @@ mod tests {
         Tag([0x0F40ED0F, 0xA69BC80F, 0x8E06F97C, 0x61E89697]),
         Tag([0x2DD072E1, 0x42B89EFE, 0xFB26B615, 0x049AA451])
     ];
+
+    /// Generated with the reference C code in `tests/reference`,
+    /// which reproduces the official 8- and 12-round vectors above.
+    const TEST_VECTORS_LTS: [Tag; 64] = [
+        Tag([0x3B1F8141, 0x1B8D6F4A, 0xF1F18630, 0x0CFAFB39]),
+        Tag([0x217ADCF9, 0x57E571CB, 0x1EACFE6D, 0x44827452]),
+        Tag([0x76E4F69C, 0xFE22F39D, 0x6486B40F, 0x7A82C1B9]),
+        Tag([0xFE71E53C, 0x64366A61, 0x9DA793D9, 0x653E3520]),
+        Tag([0xD306C14B, 0xAA4D06BB, 0x044BA436, 0x522D2DAB]),
+        Tag([0x533E7A6C, 0x6E7B6D7E, 0xD38198FE, 0xADEC9F65]),
+        Tag([0xD75CD8EA, 0x4A8D5BB1, 0x0E381BE8, 0x19800527]),
+        Tag([0xB59A8671, 0xB213412A, 0x9DE8D63D, 0x075DFAB7]),
+        Tag([0x454FF5D6, 0xA486E682, 0xC3379A1D, 0xB368BC5F]),
+        Tag([0x7B5F8595, 0x6C6C7983, 0xD8F3B8C0, 0x3ECA8F57]),
+        Tag([0xF01648C2, 0x58A48FCF, 0x5EAD5F86, 0x912DF267]),
+        Tag([0x127519FE, 0x2FCBCF15, 0xF3765B5F, 0xFF837CCD]),
+        Tag([0x47D099BE, 0x144AB823, 0xA1FC0CB6, 0x14182D58]),
+        Tag([0x6C4187E0, 0x2F7C1F7B, 0x87E72672, 0x2B95417B]),
+        Tag([0xA8366D10, 0xA4AF7535, 0x7BA99DAA, 0x91F44E58]),
+        Tag([0xAA1548AD, 0xD48889FF, 0x403938F1, 0xE9890B85]),
+        Tag([0x2C6D51C2, 0xAC83C04E, 0x2FEFB387, 0x3F71453E]),
+        Tag([0x4B0EE1F8, 0xEECF5452, 0x53470FAF, 0xAF27678C]),
+        Tag([0xEB5A2CF4, 0x94AF0630, 0xBA0195A0, 0xE5605F41]),
+        Tag([0xC2DA1BC7, 0x2BBF52F6, 0xB21F274F, 0x94DAB0F0]),
+        Tag([0xBC24C9B7, 0xA64EC6F5, 0xD4673208, 0x5CD3D1D4]),
+        Tag([0x7829EA8A, 0xC19E7D73, 0xCCDB4E59, 0x4C3040E7]),
+        Tag([0xF13AE494, 0x5B36FAE9, 0x3DF05D43, 0x0D0D2A5A]),
+        Tag([0xFD72E629, 0x23061564, 0x06C82FBB, 0x63C6AB1A]),
+        Tag([0x9C6A5726, 0x288AE5E5, 0xD545788B, 0x789B55D3]),
+        Tag([0x0D931356, 0x6B87651B, 0x36BA3F45, 0xB770C35E]),
+        Tag([0x8B459A91, 0x7144EF9E, 0xB2C37562, 0x8418B6CB]),
+        Tag([0x8E5F8502, 0x60A28685, 0x5A032BAB, 0x7B7EB556]),
+        Tag([0x3CE91032, 0xBE22E132, 0xF250039F, 0xB27FE59A]),
+        Tag([0x2FB9E39A, 0xAC3001B1, 0x70221BE9, 0xD58D9927]),
+        Tag([0x9319A06B, 0xB210C85A, 0x2AD06DEF, 0xD1BFDED7]),
+        Tag([0xD77F994D, 0xC5DF8638, 0x7631B3F0, 0x1847BE5C]),
+        Tag([0x173BB3BB, 0xF78E83FC, 0xDDAFCABB, 0x51EE2B49]),
+        Tag([0xDAAB9E0F, 0xDF7A8EE5, 0xFB697DBC, 0x64248B51]),
+        Tag([0xF2DD0B40, 0x94291EC8, 0xE9F65ABD, 0x0CC2F7F1]),
+        Tag([0xB364D712, 0xFA58CB3E, 0x6BB7E58B, 0x36EB5F90]),
+        Tag([0x3771E970, 0xB44F6ED2, 0x5BD85FD7, 0x2EBB3202]),
+        Tag([0x684629BB, 0x8BE999D3, 0x5067D116, 0x52A5BE3E]),
+        Tag([0x2695C7DB, 0xA4CFDA53, 0xD82DC269, 0xD6F9EB28]),
+        Tag([0x426ED860, 0x3590723D, 0x8D60E8E2, 0x5ADF48DC]),
+        Tag([0xECEB5AFB, 0x966817CF, 0xB76EC541, 0x393591DC]),
+        Tag([0x22933EC0, 0xD08C94F9, 0xEDFE9BB1, 0xFC6162D5]),
+        Tag([0xC65E54A9, 0x4BFFD15C, 0xE1833847, 0x0EE0DE4E]),
+        Tag([0xFE9E20B9, 0xC7B1B692, 0x8DE69519, 0x0FBD37B4]),
+        Tag([0xA134365F, 0x8C551595, 0x2855C340, 0x7E798A3F]),
+        Tag([0x01BB2BE8, 0xB37B4750, 0x3BB8F1A4, 0xE35CAD62]),
+        Tag([0x7EE626CC, 0x3F52BC5B, 0x60ED6CB2, 0xBA4070A5]),
+        Tag([0xEC4AF057, 0x1838A994, 0x52C84342, 0x0DC618C9]),
+        Tag([0xE01B9CBD, 0xDC84112F, 0xDEF108AE, 0x009B98BE]),
+        Tag([0x9DC4BDFD, 0xCC9516E6, 0x1F65C981, 0x680F4E54]),
+        Tag([0x220496C5, 0x34A505C6, 0x5871FD65, 0x26C666DB]),
+        Tag([0x49AACA0B, 0x7D6E7874, 0x254771E5, 0xE7097AFA]),
+        Tag([0x74117803, 0x35B59CF1, 0xB7870F4E, 0x71AC989B]),
+        Tag([0x6942A74A, 0xFA0F25CC, 0xA0CE8B1B, 0x3C29C41C]),
+        Tag([0x33D7D4D7, 0xF7A8CA7F, 0xA57F5C72, 0xE6C9B27C]),
+        Tag([0xA95BB4E1, 0xA8D0E1DB, 0xD63DE5B2, 0x8C48204A]),
+        Tag([0x2A35E361, 0x437B6F34, 0x25678855, 0xA94DAE82]),
+        Tag([0xE6D0E854, 0xB0EB97B0, 0xE5155757, 0x0BD96553]),
+        Tag([0x84EBF433, 0xDFCCDB91, 0x81598427, 0xAF7F0512]),
+        Tag([0xE71D0612, 0x775BDD67, 0x3E247E20, 0x1D2E54BE]),
+        Tag([0xDE386223, 0x841DCEE8, 0xDC741920, 0x4F715588]),
+        Tag([0x353E423A, 0x2B477CD4, 0x29B08826, 0xBB15C441]),
+        Tag([0x78B05CC7, 0x9C0B0650, 0xC66336BC, 0xCAA02A7E]),
+        Tag([0xCB6153F2, 0x2CE81ECD, 0x329212E0, 0xCF5F0653])
+    ];
+
+    /// Contexts for the personalization vectors: empty, one byte
+    /// short of a block, a whole block, and longer than a block.
+    const PERSONALIZATION_CONTEXTS: [&[u8]; 4] =
+        [b"", b"0123456789abcde", b"0123456789abcdef", b"example.com 2026 session"];
+
+    /// Tags of the bytes 0 to 15 under `KEY` personalized with each of
+    /// `PERSONALIZATION_CONTEXTS`.  Generated with the key derivation
+    /// rebuilt from the reference C code in the `reference` crate.
+    const PERSONALIZATION_VECTORS_8: [Tag; 4] = [
+        Tag([0x6026EABF, 0x6A426FDE, 0x31C62BA5, 0x91A690E7]),
+        Tag([0xD7F30092, 0xA6C78A4F, 0x43AD16CA, 0x5DCAA98A]),
+        Tag([0x1233712E, 0x87CFC480, 0xF1789D98, 0xE58DB784]),
+        Tag([0xB9A3081A, 0x283B262C, 0x46DC469A, 0xD56821F5])
+    ];
+
+    const PERSONALIZATION_VECTORS_12: [Tag; 4] = [
+        Tag([0x3B658AFB, 0xC0A0F915, 0xC7ED309E, 0xA09665DF]),
+        Tag([0x41166546, 0x2F2086EE, 0x85180397, 0x27F7D80E]),
+        Tag([0x5AF69CE4, 0x2DD7E25C, 0x6477E4EC, 0x38134D9F]),
+        Tag([0x1E52B21D, 0x5AAA41B3, 0xD25B5297, 0x803C42E0])
+    ];
+
+    const PERSONALIZATION_VECTORS_LTS: [Tag; 4] = [
+        Tag([0xCF57F519, 0x8A01804D, 0x3EAC6D8A, 0x759C2EFC]),
+        Tag([0x005F1A92, 0x115331C1, 0xE027C272, 0xF6D997E6]),
+        Tag([0x9702AD4E, 0x402F63CB, 0xF2EEE995, 0x997726E8]),
+        Tag([0x60223EE7, 0x43A4EF01, 0xFB1AC84A, 0x4A19607F])
+    ];
 }
 
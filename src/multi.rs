@@ -0,0 +1,203 @@
+//! Interleaved processing of several messages at once.
+//!
+//! Each Chaskey round is a chain of additions, rotations and XORs in
+//! which every step depends on the previous one, so a single
+//! `Digester` leaves most of a modern processor's execution units
+//! idle.  Running the rounds of two or four independent states side
+//! by side hides that latency, even without SIMD instructions.
+
+use core::Permutation;
+use std::array;
+use std::convert::TryInto;
+use std::marker::PhantomData;
+use super::{make_keys, Keys, Tag};
+use util::{xor_u32x4, xor_u8x16};
+use zeroize::Zeroize;
+
+
+/// A Chaskey MAC that authenticates `N` messages at once, each with
+/// its own key.  The tags it produces are the same as those of `N`
+/// separate `Digester`s.
+///
+/// ```
+/// # extern crate chaskey;
+/// use chaskey::{Chaskey, Digester, MultiDigester};
+///
+/// # fn main() {
+/// let keys = [[1, 2, 3, 4], [5, 6, 7, 8]];
+/// let mac: MultiDigester<Chaskey, 2> = MultiDigester::new(keys);
+/// let tags = mac.digest([b"Hello world!", b"Goodbye, cruel world!"]);
+///
+/// let mut single: Digester<Chaskey> = Digester::new(keys[1]);
+/// single.write(b"Goodbye, cruel world!");
+/// assert!(tags[1] == single.finish());
+/// # }
+/// ```
+///
+/// The key schedules are wiped when the `MultiDigester` is dropped,
+/// and `digest` wipes its intermediate states.
+pub struct MultiDigester<P, const N: usize> {
+    permutation: PhantomData<P>,
+    keys: [Keys; N]
+}
+
+impl<P: Permutation, const N: usize> MultiDigester<P, N> {
+    /// Initialize a new multi-message digester with one key per
+    /// message.
+    pub fn new(keys: [[u32; 4]; N]) -> MultiDigester<P, N> {
+        MultiDigester {
+            permutation: PhantomData,
+            keys: array::from_fn(|lane| make_keys(keys[lane]))
+        }
+    }
+
+    /// Compute the tags of `N` messages, which may have different
+    /// lengths.  The `i`th message is authenticated with the `i`th
+    /// key.
+    pub fn digest(&self, messages: [&[u8]; N]) -> [Tag; N] {
+        let mut states: [[u32; 4]; N] =
+            array::from_fn(|lane| self.keys[lane].key);
+
+        // Number of blocks in each message that precede its final
+        // block (which may be a full or a partial one).
+        let blocks: [usize; N] =
+            array::from_fn(|lane| messages[lane].len().saturating_sub(1) / 16);
+
+        // The blocks that every message has are processed in
+        // lockstep...
+        let common = blocks.iter().cloned().min().unwrap_or(0);
+        for b in 0..common {
+            for lane in 0..N {
+                xor_u8x16(&mut states[lane], block(messages[lane], b));
+            }
+            P::permute_lanes(&mut states);
+        }
+
+        // ...and then the longer messages catch up on their own.
+        for lane in 0..N {
+            for b in common..blocks[lane] {
+                xor_u8x16(&mut states[lane], block(messages[lane], b));
+                P::permute(&mut states[lane]);
+            }
+        }
+
+        // Every message has exactly one final block left.
+        let mut subkeys = [[0u32; 4]; N];
+        for lane in 0..N {
+            let rest = &messages[lane][blocks[lane] * 16..];
+            let mut last = [0u8; 16];
+            last[..rest.len()].copy_from_slice(rest);
+            if rest.len() == 16 {
+                subkeys[lane] = self.keys[lane].k1;
+            } else {
+                last[rest.len()] = 0x01;
+                subkeys[lane] = self.keys[lane].k2;
+            }
+            xor_u8x16(&mut states[lane], &last);
+            xor_u32x4(&mut states[lane], &subkeys[lane]);
+            last.zeroize();
+        }
+        P::permute_lanes(&mut states);
+        for lane in 0..N {
+            xor_u32x4(&mut states[lane], &subkeys[lane]);
+        }
+
+        let tags = array::from_fn(|lane| Tag(states[lane]));
+        states.zeroize();
+        subkeys.zeroize();
+        tags
+    }
+}
+
+/// The `b`th 16-byte block of `message`.
+#[inline(always)]
+fn block(message: &[u8], b: usize) -> &[u8; 16] {
+    message[b * 16..(b + 1) * 16].try_into().unwrap()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use core::*;
+    use super::MultiDigester;
+    use quickcheck::{Arbitrary, Gen, quickcheck};
+    use {Digester, Tag};
+
+    /// A key and a message to authenticate with it.
+    #[derive(Clone, Debug)]
+    struct Lane {
+        key: [u32; 4],
+        msg: Vec<u8>
+    }
+
+    impl Arbitrary for Lane {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            Lane {
+                key: [g.gen(), g.gen(), g.gen(), g.gen()],
+                msg: Arbitrary::arbitrary(g)
+            }
+        }
+
+        fn shrink(&self) -> Box<dyn Iterator<Item=Self>> {
+            let key = self.key;
+            Box::new(self.msg.shrink().map(move |msg| Lane { key, msg }))
+        }
+    }
+
+    /// Whether `tag` is what a single `Digester` computes for `lane`.
+    fn check<P: Permutation>(tag: &Tag, lane: &Lane) -> bool {
+        let mut digester: Digester<P> = Digester::new(lane.key);
+        digester.write(&lane.msg);
+        *tag == digester.finish()
+    }
+
+    #[test]
+    fn multi2_8() {
+        multi2::<Chaskey>();
+    }
+
+    #[test]
+    fn multi2_12() {
+        multi2::<Chaskey12>();
+    }
+
+    #[test]
+    fn multi2_16() {
+        multi2::<ChaskeyLTS>();
+    }
+
+    fn multi2<P: Permutation>() {
+        fn prop<P: Permutation>(a: Lane, b: Lane) -> bool {
+            let mac: MultiDigester<P, 2> = MultiDigester::new([a.key, b.key]);
+            let tags = mac.digest([&a.msg, &b.msg]);
+            check::<P>(&tags[0], &a) && check::<P>(&tags[1], &b)
+        }
+        quickcheck(prop::<P> as fn(Lane, Lane) -> bool);
+    }
+
+    #[test]
+    fn multi4_8() {
+        multi4::<Chaskey>();
+    }
+
+    #[test]
+    fn multi4_12() {
+        multi4::<Chaskey12>();
+    }
+
+    #[test]
+    fn multi4_16() {
+        multi4::<ChaskeyLTS>();
+    }
+
+    fn multi4<P: Permutation>() {
+        fn prop<P: Permutation>(a: Lane, b: Lane, c: Lane, d: Lane) -> bool {
+            let mac: MultiDigester<P, 4> =
+                MultiDigester::new([a.key, b.key, c.key, d.key]);
+            let tags = mac.digest([&a.msg, &b.msg, &c.msg, &d.msg]);
+            check::<P>(&tags[0], &a) && check::<P>(&tags[1], &b)
+                && check::<P>(&tags[2], &c) && check::<P>(&tags[3], &d)
+        }
+        quickcheck(prop::<P> as fn(Lane, Lane, Lane, Lane) -> bool);
+    }
+}
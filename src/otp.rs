@@ -0,0 +1,412 @@
+//! One-time codes in the style of HOTP (RFC 4226) and TOTP (RFC 6238),
+//! with Chaskey in place of HMAC-SHA-1.
+//!
+//! The code for a counter value `C` is computed as in HOTP:
+//!
+//! ```text
+//! T      = Chaskey(K', C as 8 bytes, big-endian)
+//! offset = T[15] mod 12
+//! P      = T[offset..offset + 4], big-endian, with the top bit cleared
+//! code   = P mod 10^digits, written with exactly `digits` digits
+//! ```
+//!
+//! `K'` is the key personalized with `"chaskey-otp-v1"`
+//! (`Digester::with_personalization`), so the key can also be used
+//! for other MACs.  (RFC 4226 takes the offset from the low 4 bits of
+//! the last byte of a 20-byte HMAC; a Chaskey tag has only 16 bytes,
+//! hence the `mod 12`.)  Codes have 6 to 8 digits.
+//!
+//! For TOTP, the counter is the number of `step`-second intervals
+//! since the Unix epoch, by an injectable `Clock`.
+//!
+//! ```
+//! # extern crate chaskey;
+//! use chaskey::Chaskey12;
+//! use chaskey::clock::ManualClock;
+//! use chaskey::otp::Totp;
+//!
+//! # fn main() {
+//! let clock = ManualClock::new(1_700_000_000);
+//! let key = [1, 2, 3, 4];
+//! let mut device: Totp<Chaskey12, _> = Totp::with_clock(key, 6, 30, &clock);
+//! let mut server: Totp<Chaskey12, _> = Totp::with_clock(key, 6, 30, &clock);
+//!
+//! let code = device.now();
+//! assert_eq!(code.len(), 6);
+//! assert!(server.verify(&code).is_ok());
+//! // Each code is accepted once.
+//! assert!(server.verify(&code).is_err());
+//! # }
+//! ```
+
+use clock::{Clock, SystemClock};
+use core::Permutation;
+use std::error::Error;
+use std::fmt;
+use subtle::{Choice, ConditionallySelectable, ConstantTimeEq};
+use Digester;
+
+/// The personalization context of the codes.
+const CONTEXT: &[u8] = b"chaskey-otp-v1";
+
+
+/// Generates counter-based one-time codes.
+pub struct Hotp<P> {
+    digester: Digester<P>,
+    digits: u32
+}
+
+impl<P: Permutation> Hotp<P> {
+    /// A generator of `digits`-digit codes.
+    ///
+    /// # Panics
+    ///
+    /// Unless `digits` is between 6 and 8.
+    pub fn new(key: [u32; 4], digits: u32) -> Hotp<P> {
+        assert!((6..=8).contains(&digits), "invalid number of OTP digits {}", digits);
+        Hotp { digester: Digester::with_personalization(key, CONTEXT), digits }
+    }
+
+    /// The number of digits in each code.
+    pub fn digits(&self) -> u32 {
+        self.digits
+    }
+
+    /// The code for counter value `counter`.
+    pub fn code(&mut self, counter: u64) -> String {
+        format!("{:0width$}", self.value(counter), width = self.digits as usize)
+    }
+
+    /// The code as a number.
+    fn value(&mut self, counter: u64) -> u32 {
+        self.digester.reset();
+        self.digester.write(&counter.to_be_bytes());
+        let tag = self.digester.finish().raw_bytes();
+        let offset = tag[15] as usize % 12;
+        let p = u32::from_be_bytes([tag[offset], tag[offset + 1], tag[offset + 2], tag[offset + 3]]);
+        (p & 0x7fff_ffff) % 10u32.pow(self.digits)
+    }
+
+    /// Find the first counter value from `first` to `last` whose code
+    /// (followed by `next`'s, if given) matches.  All the codes in
+    /// the range are computed and compared in constant time, so the
+    /// time taken doesn't depend on which one matched.
+    fn search(&mut self, code: &str, next: Option<&str>, first: u64, last: u64)
+              -> Result<u64, OtpError> {
+        let code = self.parse(code)?;
+        let next = match next {
+            Some(next) => Some(self.parse(next)?),
+            None => None
+        };
+        let mut found = Choice::from(0);
+        let mut matched = 0u64;
+        let mut value = self.value(first);
+        for counter in first..=last {
+            let mut matches = value.ct_eq(&code);
+            if let Some(next) = next {
+                value = self.value(counter.saturating_add(1));
+                matches &= value.ct_eq(&next);
+            } else if counter < last {
+                value = self.value(counter + 1);
+            }
+            matched.conditional_assign(&counter, matches & !found);
+            found |= matches;
+        }
+        if bool::from(found) {
+            Ok(matched)
+        } else {
+            Err(OtpError::Mismatch)
+        }
+    }
+
+    /// Parse a code as entered, which must have exactly `digits`
+    /// decimal digits.
+    fn parse(&self, code: &str) -> Result<u32, OtpError> {
+        if code.len() != self.digits as usize || !code.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(OtpError::Malformed);
+        }
+        Ok(code.parse().unwrap())
+    }
+}
+
+
+/// Verifies counter-based codes, keeping the counter in step with the
+/// device that generates them.
+pub struct HotpVerifier<P> {
+    hotp: Hotp<P>,
+    counter: u64,
+    look_ahead: u64
+}
+
+impl<P: Permutation> HotpVerifier<P> {
+    /// A verifier that expects the code for `counter` next, and
+    /// accepts codes up to `look_ahead` counter values beyond it, in
+    /// case some codes were generated but never entered.
+    ///
+    /// # Panics
+    ///
+    /// Unless `digits` is between 6 and 8.
+    pub fn new(key: [u32; 4], digits: u32, counter: u64, look_ahead: u64) -> HotpVerifier<P> {
+        HotpVerifier { hotp: Hotp::new(key, digits), counter, look_ahead }
+    }
+
+    /// The counter value of the next expected code.
+    pub fn counter(&self) -> u64 {
+        self.counter
+    }
+
+    /// Verify a code, and return its counter value.  On success, the
+    /// counter moves past it, so neither it nor earlier codes are
+    /// accepted again.
+    pub fn verify(&mut self, code: &str) -> Result<u64, OtpError> {
+        let last = self.counter.saturating_add(self.look_ahead);
+        let counter = self.hotp.search(code, None, self.counter, last)?;
+        self.counter = counter.saturating_add(1);
+        Ok(counter)
+    }
+
+    /// Resynchronize with a device that has moved beyond the
+    /// look-ahead window: look for two consecutive codes `first` and
+    /// `second` within `window` counter values of the expected one.
+    /// Returns the counter value of `second`, after which the counter
+    /// continues.
+    pub fn resync(&mut self, first: &str, second: &str, window: u64) -> Result<u64, OtpError> {
+        let last = self.counter.saturating_add(window);
+        let counter = self.hotp.search(first, Some(second), self.counter, last)?;
+        self.counter = counter.saturating_add(2);
+        Ok(counter.saturating_add(1))
+    }
+}
+
+
+/// Generates and verifies time-based codes.
+pub struct Totp<P, C = SystemClock> {
+    hotp: Hotp<P>,
+    step: u64,
+    window: u64,
+    clock: C,
+    drift: i64,
+    last: Option<u64>
+}
+
+impl<P: Permutation> Totp<P, SystemClock> {
+    /// Codes for `step`-second intervals, by the system clock.
+    ///
+    /// # Panics
+    ///
+    /// Unless `digits` is between 6 and 8, and `step` is positive.
+    pub fn new(key: [u32; 4], digits: u32, step: u64) -> Totp<P, SystemClock> {
+        Totp::with_clock(key, digits, step, SystemClock)
+    }
+}
+
+impl<P: Permutation, C: Clock> Totp<P, C> {
+    /// Codes for `step`-second intervals, by `clock`.  Verification
+    /// accepts codes one interval either side of the current one.
+    ///
+    /// # Panics
+    ///
+    /// Unless `digits` is between 6 and 8, and `step` is positive.
+    pub fn with_clock(key: [u32; 4], digits: u32, step: u64, clock: C) -> Totp<P, C> {
+        assert!(step > 0, "TOTP step must be positive");
+        Totp { hotp: Hotp::new(key, digits), step, window: 1, clock, drift: 0, last: None }
+    }
+
+    /// Accept codes up to `window` intervals either side of the
+    /// current one (after allowing for drift).
+    pub fn set_window(&mut self, window: u64) {
+        self.window = window;
+    }
+
+    /// How many intervals the other side's clock was found to be
+    /// ahead (or, if negative, behind) this one, when a code was last
+    /// accepted.  Verification centres its window on this.
+    pub fn drift(&self) -> i64 {
+        self.drift
+    }
+
+    /// The current interval number.
+    pub fn counter(&self) -> u64 {
+        self.clock.now() / self.step
+    }
+
+    /// The code for the current interval.
+    pub fn now(&mut self) -> String {
+        let counter = self.counter();
+        self.hotp.code(counter)
+    }
+
+    /// The code for the interval containing time `time`.
+    pub fn at(&mut self, time: u64) -> String {
+        self.hotp.code(time / self.step)
+    }
+
+    /// Verify a code, and return its interval number.  Codes from the
+    /// interval of the last accepted code, or earlier ones, are
+    /// rejected, so each code can be used once.  An accepted code
+    /// that was not from the expected interval updates the drift.
+    pub fn verify(&mut self, code: &str) -> Result<u64, OtpError> {
+        let now = self.counter();
+        let centre = if self.drift < 0 {
+            now.saturating_sub(self.drift.unsigned_abs())
+        } else {
+            now.saturating_add(self.drift as u64)
+        };
+        let mut first = centre.saturating_sub(self.window);
+        if let Some(last) = self.last {
+            first = first.max(last.saturating_add(1));
+        }
+        let last = centre.saturating_add(self.window);
+        if first > last {
+            return Err(OtpError::Mismatch);
+        }
+        let counter = self.hotp.search(code, None, first, last)?;
+        self.last = Some(counter);
+        self.drift = counter as i64 - now as i64;
+        Ok(counter)
+    }
+}
+
+
+/// Why a one-time code was rejected.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OtpError {
+    /// The code doesn't have the right number of decimal digits.
+    Malformed,
+    /// The code doesn't match any accepted counter value.
+    Mismatch
+}
+
+impl fmt::Display for OtpError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            OtpError::Malformed => write!(f, "malformed one-time code"),
+            OtpError::Mismatch => write!(f, "incorrect one-time code")
+        }
+    }
+}
+
+impl Error for OtpError { }
+
+
+#[cfg(test)]
+mod tests {
+    use clock::ManualClock;
+    use core::*;
+    use quickcheck::quickcheck;
+    use super::{Hotp, HotpVerifier, OtpError, Totp, CONTEXT};
+    use Digester;
+
+    const KEY: [u32; 4] = [0x833D3433, 0x009F389F, 0x2398E64F, 0x417ACF39];
+
+    #[test]
+    fn truncation() {
+        let mut hotp: Hotp<Chaskey> = Hotp::new(KEY, 8);
+        for counter in 0..100 {
+            let mut d: Digester<Chaskey> = Digester::with_personalization(KEY, CONTEXT);
+            d.write(&[0, 0, 0, 0, 0, 0, 0, counter as u8]);
+            let tag = d.finish().raw_bytes();
+            let offset = tag[15] as usize % 12;
+            let p = (tag[offset] as u32 & 0x7f) << 24 | (tag[offset + 1] as u32) << 16
+                | (tag[offset + 2] as u32) << 8 | tag[offset + 3] as u32;
+            assert_eq!(hotp.code(counter), format!("{:08}", p % 100_000_000));
+        }
+        // Leading zeros are kept.
+        let mut hotp: Hotp<Chaskey> = Hotp::new(KEY, 6);
+        assert!((0..1000).map(|c| hotp.code(c)).any(|code| code.starts_with('0')));
+        assert!((0..1000).all(|c| hotp.code(c).len() == 6));
+    }
+
+    #[test]
+    fn hotp_8() {
+        hotp::<Chaskey>();
+    }
+
+    #[test]
+    fn hotp_12() {
+        hotp::<Chaskey12>();
+    }
+
+    #[test]
+    fn hotp_16() {
+        hotp::<ChaskeyLTS>();
+    }
+
+    /// A device that skips up to `look_ahead` codes between entered
+    /// ones stays in step with the verifier.
+    fn hotp<P: Permutation>() {
+        fn prop<P: Permutation>(skips: Vec<u8>) -> bool {
+            // 8 digits, so that codes in the window rarely collide.
+            let mut device: Hotp<P> = Hotp::new(KEY, 8);
+            let mut verifier: HotpVerifier<P> = HotpVerifier::new(KEY, 8, 0, 3);
+            let mut counter = 0;
+            for skip in skips {
+                counter += skip as u64 % 4;
+                let code = device.code(counter);
+                if verifier.verify(&code) != Ok(counter) || verifier.verify(&code).is_ok() {
+                    return false;
+                }
+                counter += 1;
+            }
+            true
+        }
+        quickcheck(prop::<P> as fn(Vec<u8>) -> bool);
+    }
+
+    #[test]
+    fn resync() {
+        let mut device: Hotp<Chaskey> = Hotp::new(KEY, 6);
+        let mut verifier: HotpVerifier<Chaskey> = HotpVerifier::new(KEY, 6, 10, 5);
+        assert_eq!(verifier.verify(&device.code(40)), Err(OtpError::Mismatch));
+        assert_eq!(verifier.resync(&device.code(40), &device.code(42), 50), Err(OtpError::Mismatch));
+        assert_eq!(verifier.resync(&device.code(40), &device.code(41), 20), Err(OtpError::Mismatch));
+        assert_eq!(verifier.counter(), 10);
+        assert_eq!(verifier.resync(&device.code(40), &device.code(41), 50), Ok(41));
+        assert_eq!(verifier.counter(), 42);
+        assert_eq!(verifier.verify(&device.code(41)), Err(OtpError::Mismatch));
+        assert_eq!(verifier.verify(&device.code(43)), Ok(43));
+    }
+
+    #[test]
+    fn totp() {
+        let clock = ManualClock::new(1_700_000_000);
+        let mut device: Totp<Chaskey, _> = Totp::with_clock(KEY, 8, 30, &clock);
+        let mut server: Totp<Chaskey, _> = Totp::with_clock(KEY, 8, 30, &clock);
+        let counter = 1_700_000_000 / 30;
+        assert_eq!(device.now(), Hotp::<Chaskey>::new(KEY, 8).code(counter));
+        assert_eq!(device.at(1_700_000_000 - 30), Hotp::<Chaskey>::new(KEY, 8).code(counter - 1));
+
+        // One interval late is fine, two is not.
+        let old = device.at(1_700_000_000 - 60);
+        let late = device.at(1_700_000_000 - 30);
+        assert_eq!(server.verify(&old), Err(OtpError::Mismatch));
+        assert_eq!(server.verify(&late), Ok(counter - 1));
+        assert_eq!(server.drift(), -1);
+        // Reuse is rejected.
+        assert_eq!(server.verify(&late), Err(OtpError::Mismatch));
+
+        // The window follows the drift: a device running 3 intervals
+        // fast is accepted once it has been seen 2 fast.
+        let mut server: Totp<Chaskey, _> = Totp::with_clock(KEY, 8, 30, &clock);
+        server.set_window(2);
+        assert_eq!(server.verify(&device.at(1_700_000_000 + 60)), Ok(counter + 2));
+        clock.advance(30);
+        assert_eq!(server.verify(&device.at(1_700_000_030 + 90)), Ok(counter + 4));
+        assert_eq!(server.drift(), 3);
+    }
+
+    #[test]
+    fn errors() {
+        let mut verifier: HotpVerifier<Chaskey> = HotpVerifier::new(KEY, 6, 0, 3);
+        for code in ["12345", "1234567", "12345a", " 123456", "+12345", "１２３４５６"].iter() {
+            assert_eq!(verifier.verify(code), Err(OtpError::Malformed), "{:?}", code);
+        }
+        assert_eq!(verifier.counter(), 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn too_few_digits() {
+        let _: Hotp<Chaskey> = Hotp::new(KEY, 5);
+    }
+}
@@ -0,0 +1,457 @@
+//! Secured PDUs in the style of AUTOSAR SecOC (Secure Onboard
+//! Communication), authenticated with Chaskey.
+//!
+//! A sender turns a payload (the "authentic PDU") into a secured PDU
+//! by appending the low bits of a freshness value (a message counter)
+//! and the leading bits of a MAC:
+//!
+//! ```text
+//! secured PDU   = payload || truncated freshness || truncated MAC
+//! MAC           = Chaskey(key, data ID || payload || freshness)
+//! ```
+//!
+//! * The data ID is 2 bytes and the full freshness value 8 bytes, both
+//!   big-endian, in the MAC input.
+//! * The truncated freshness value is the low `freshness_bits` (0 to
+//!   64) bits of the freshness value, and the truncated MAC the first
+//!   `mac_bits` (1 to 128) bits of the tag's bytes (`Tag::raw_bytes`).
+//!   Both are packed most significant bit first, right after the
+//!   payload, with zero bits to fill the last byte.  So neither needs
+//!   to be a whole number of bytes.
+//!
+//! A receiver keeps the last freshness value it accepted.  It
+//! reconstructs the full freshness value of a secured PDU as the
+//! smallest value after that one whose low bits match the truncated
+//! freshness, and accepts the PDU only if that value is at most
+//! `acceptance_window` ahead and the MAC matches.  This rejects
+//! replays, while tolerating up to `acceptance_window - 1` lost PDUs
+//! in a row.
+//!
+//! ```
+//! # extern crate chaskey;
+//! use chaskey::Chaskey;
+//! use chaskey::secoc::{Config, Receiver, Sender};
+//!
+//! # fn main() {
+//! let key = [1, 2, 3, 4];
+//! // Data ID 0x0123, 4 bits of freshness and 28 bits of MAC.
+//! let config = Config::new(0x0123, 4, 28, 5).unwrap();
+//! let mut sender: Sender<Chaskey> = Sender::new(key, config, 1);
+//! let mut receiver: Receiver<Chaskey> = Receiver::new(key, config, 0);
+//!
+//! let pdu = sender.secure(b"brake").unwrap();
+//! assert_eq!(pdu.len(), 5 + 4);
+//! assert_eq!(receiver.verify(&pdu), Ok(&b"brake"[..]));
+//! // Replays are rejected.
+//! assert!(receiver.verify(&pdu).is_err());
+//! # }
+//! ```
+
+use core::Permutation;
+use std::error::Error;
+use std::fmt;
+use subtle::ConstantTimeEq;
+use Digester;
+
+
+/// The parameters of a secured PDU, shared by sender and receiver.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Config {
+    data_id: u16,
+    freshness_bits: u32,
+    mac_bits: u32,
+    acceptance_window: u64
+}
+
+impl Config {
+    /// Make a configuration.  This fails unless `freshness_bits` is
+    /// at most 64, `mac_bits` is between 1 and 128, and
+    /// `acceptance_window` is at least 1.  With truncated freshness
+    /// values, the window must also be at most `2^freshness_bits`,
+    /// since larger jumps can't be told apart.
+    pub fn new(data_id: u16, freshness_bits: u32, mac_bits: u32, acceptance_window: u64)
+               -> Result<Config, SecOcError> {
+        let window_ok = acceptance_window >= 1
+            && (freshness_bits >= 64 || acceptance_window <= 1 << freshness_bits);
+        if freshness_bits > 64 || mac_bits == 0 || mac_bits > 128 || !window_ok {
+            return Err(SecOcError::InvalidConfig);
+        }
+        Ok(Config { data_id, freshness_bits, mac_bits, acceptance_window })
+    }
+
+    pub fn data_id(&self) -> u16 {
+        self.data_id
+    }
+
+    pub fn freshness_bits(&self) -> u32 {
+        self.freshness_bits
+    }
+
+    pub fn mac_bits(&self) -> u32 {
+        self.mac_bits
+    }
+
+    pub fn acceptance_window(&self) -> u64 {
+        self.acceptance_window
+    }
+
+    /// The number of bytes a secured PDU adds to its payload.
+    pub fn overhead(&self) -> usize {
+        (self.freshness_bits + self.mac_bits).div_ceil(8) as usize
+    }
+
+    /// The mask of the freshness bits sent in secured PDUs.
+    fn freshness_mask(&self) -> u64 {
+        if self.freshness_bits >= 64 { !0 } else { (1 << self.freshness_bits) - 1 }
+    }
+
+    /// The truncated MAC of a PDU, left-aligned, with the bits after
+    /// `mac_bits` cleared.
+    fn mac<P: Permutation>(&self, digester: &mut Digester<P>,
+                           payload: &[u8], freshness: u64) -> [u8; 16] {
+        digester.reset();
+        digester.write(&self.data_id.to_be_bytes());
+        digester.write(payload);
+        digester.write(&freshness.to_be_bytes());
+        let mut mac = digester.finish().raw_bytes();
+        for (i, byte) in mac.iter_mut().enumerate() {
+            let keep = (self.mac_bits as usize).saturating_sub(8 * i).min(8);
+            *byte &= !(0xffu8.checked_shr(keep as u32).unwrap_or(0));
+        }
+        mac
+    }
+}
+
+
+/// Builds secured PDUs, counting freshness values up from a starting
+/// value.
+pub struct Sender<P> {
+    config: Config,
+    digester: Digester<P>,
+    freshness: u64
+}
+
+impl<P: Permutation> Sender<P> {
+    /// Make a sender whose first PDU will have freshness value
+    /// `freshness`.
+    pub fn new(key: [u32; 4], config: Config, freshness: u64) -> Sender<P> {
+        Sender { config, digester: Digester::new(key), freshness }
+    }
+
+    /// The freshness value of the next PDU.
+    pub fn freshness(&self) -> u64 {
+        self.freshness
+    }
+
+    /// Build the secured PDU for `payload`, and advance the freshness
+    /// value.  Fails only when the freshness values are used up.
+    pub fn secure(&mut self, payload: &[u8]) -> Result<Vec<u8>, SecOcError> {
+        let freshness = self.freshness;
+        self.freshness = freshness.checked_add(1).ok_or(SecOcError::FreshnessExhausted)?;
+        Ok(secure_with(&self.config, &mut self.digester, payload, freshness))
+    }
+}
+
+/// Build a secured PDU with a given freshness value.  For senders
+/// that manage freshness values themselves; a `Sender` does this for
+/// a simple counter.
+pub fn secure<P: Permutation>(key: [u32; 4], config: &Config,
+                              payload: &[u8], freshness: u64) -> Vec<u8> {
+    secure_with(config, &mut Digester::<P>::new(key), payload, freshness)
+}
+
+fn secure_with<P: Permutation>(config: &Config, digester: &mut Digester<P>,
+                               payload: &[u8], freshness: u64) -> Vec<u8> {
+    let mac = config.mac(digester, payload, freshness);
+    let mut pdu = Vec::with_capacity(payload.len() + config.overhead());
+    pdu.extend_from_slice(payload);
+    let mut trailer = BitWriter::new(&mut pdu);
+    trailer.push(freshness & config.freshness_mask(), config.freshness_bits);
+    for (i, byte) in mac.iter().enumerate() {
+        let bits = (config.mac_bits as usize).saturating_sub(8 * i).min(8) as u32;
+        trailer.push((byte >> (8 - bits.max(1))) as u64, bits);
+    }
+    pdu
+}
+
+
+/// Verifies secured PDUs, keeping track of the last accepted
+/// freshness value.
+pub struct Receiver<P> {
+    config: Config,
+    digester: Digester<P>,
+    latest: u64
+}
+
+impl<P: Permutation> Receiver<P> {
+    /// Make a receiver that will only accept freshness values after
+    /// `latest`.
+    pub fn new(key: [u32; 4], config: Config, latest: u64) -> Receiver<P> {
+        Receiver { config, digester: Digester::new(key), latest }
+    }
+
+    /// The last accepted freshness value.
+    pub fn latest(&self) -> u64 {
+        self.latest
+    }
+
+    /// Verify a secured PDU, and return its payload.  On success the
+    /// PDU's freshness value becomes the latest one; on failure
+    /// nothing changes.
+    pub fn verify<'a>(&mut self, pdu: &'a [u8]) -> Result<&'a [u8], SecOcError> {
+        let config = &self.config;
+        let payload_len = pdu.len().checked_sub(config.overhead()).ok_or(SecOcError::TooShort)?;
+        let (payload, trailer) = pdu.split_at(payload_len);
+
+        let mut reader = BitReader::new(trailer);
+        let truncated = reader.pull(config.freshness_bits);
+        let mut received = [0u8; 16];
+        for (i, byte) in received.iter_mut().enumerate() {
+            let bits = (config.mac_bits as usize).saturating_sub(8 * i).min(8) as u32;
+            *byte = (reader.pull(bits) << (8 - bits.max(1))) as u8;
+        }
+
+        let freshness = reconstruct(self.latest, truncated, config)
+            .ok_or(SecOcError::Freshness)?;
+        let expected = config.mac(&mut self.digester, payload, freshness);
+        if !bool::from(expected.ct_eq(&received)) {
+            return Err(SecOcError::Authentication);
+        }
+        self.latest = freshness;
+        Ok(payload)
+    }
+}
+
+/// The smallest freshness value after `latest` whose low bits are
+/// `truncated`, if it is within the acceptance window.
+fn reconstruct(latest: u64, truncated: u64, config: &Config) -> Option<u64> {
+    let mask = config.freshness_mask();
+    let mut candidate = (latest & !mask) | truncated;
+    if candidate <= latest {
+        candidate = candidate.checked_add(mask.checked_add(1)?)?;
+    }
+    if candidate - latest <= config.acceptance_window {
+        Some(candidate)
+    } else {
+        None
+    }
+}
+
+
+/// Appends bits to a byte vector, most significant first.
+struct BitWriter<'a> {
+    bytes: &'a mut Vec<u8>,
+    used: u32
+}
+
+impl<'a> BitWriter<'a> {
+    fn new(bytes: &'a mut Vec<u8>) -> BitWriter<'a> {
+        BitWriter { bytes, used: 8 }
+    }
+
+    /// Append the low `bits` bits of `value`.
+    fn push(&mut self, value: u64, bits: u32) {
+        for i in (0..bits).rev() {
+            if self.used == 8 {
+                self.bytes.push(0);
+                self.used = 0;
+            }
+            let bit = ((value >> i) & 1) as u8;
+            *self.bytes.last_mut().unwrap() |= bit << (7 - self.used);
+            self.used += 1;
+        }
+    }
+}
+
+/// Reads bits from a byte slice, most significant first.
+struct BitReader<'a> {
+    bytes: &'a [u8],
+    pos: usize
+}
+
+impl<'a> BitReader<'a> {
+    fn new(bytes: &'a [u8]) -> BitReader<'a> {
+        BitReader { bytes, pos: 0 }
+    }
+
+    /// Read `bits` (at most 64) bits.
+    fn pull(&mut self, bits: u32) -> u64 {
+        let mut value = 0u64;
+        for _ in 0..bits {
+            let bit = (self.bytes[self.pos / 8] >> (7 - self.pos % 8)) & 1;
+            value = value << 1 | bit as u64;
+            self.pos += 1;
+        }
+        value
+    }
+}
+
+
+/// Errors from configuring, building or verifying secured PDUs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SecOcError {
+    /// The parameters given to `Config::new` are out of range.
+    InvalidConfig,
+    /// The sender's freshness counter has reached its maximum.
+    FreshnessExhausted,
+    /// The PDU is too short to hold the freshness value and MAC.
+    TooShort,
+    /// The PDU's freshness value is not within the acceptance window:
+    /// it is a replay, or too many PDUs were lost.
+    Freshness,
+    /// The MAC doesn't match.
+    Authentication
+}
+
+impl fmt::Display for SecOcError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SecOcError::InvalidConfig =>
+                write!(f, "invalid SecOC configuration"),
+            SecOcError::FreshnessExhausted =>
+                write!(f, "freshness counter exhausted"),
+            SecOcError::TooShort =>
+                write!(f, "secured PDU too short"),
+            SecOcError::Freshness =>
+                write!(f, "freshness value outside the acceptance window"),
+            SecOcError::Authentication =>
+                write!(f, "MAC verification failed")
+        }
+    }
+}
+
+impl Error for SecOcError { }
+
+
+#[cfg(test)]
+mod tests {
+    use core::*;
+    use quickcheck::quickcheck;
+    use super::{secure, Config, Receiver, SecOcError, Sender};
+    use Digester;
+
+    const KEY: [u32; 4] = [0x833D3433, 0x009F389F, 0x2398E64F, 0x417ACF39];
+
+    #[test]
+    fn layout() {
+        // Byte-aligned fields: the trailer is the low freshness byte,
+        // then the first 3 bytes of the tag.
+        let config = Config::new(0x1234, 8, 24, 1).unwrap();
+        let pdu = secure::<Chaskey>(KEY, &config, b"abc", 0x0102030405060708);
+        let mut d: Digester<Chaskey> = Digester::new(KEY);
+        d.write(&[0x12, 0x34]);
+        d.write(b"abc");
+        d.write(&[1, 2, 3, 4, 5, 6, 7, 8]);
+        let tag = d.finish().raw_bytes();
+        assert_eq!(pdu, [b'a', b'b', b'c', 0x08, tag[0], tag[1], tag[2]]);
+
+        // 4 freshness bits and 10 MAC bits share bytes, and the last
+        // two bits are padding.
+        let config = Config::new(0x1234, 4, 10, 1).unwrap();
+        let pdu = secure::<Chaskey>(KEY, &config, b"abc", 0x0102030405060708);
+        assert_eq!(pdu.len(), 5);
+        assert_eq!(pdu[3], 0x80 | tag[0] >> 4);
+        assert_eq!(pdu[4], (tag[0] << 4) | (tag[1] >> 6 << 2));
+    }
+
+    #[test]
+    fn round_trip_8() {
+        round_trip::<Chaskey>();
+    }
+
+    #[test]
+    fn round_trip_12() {
+        round_trip::<Chaskey12>();
+    }
+
+    #[test]
+    fn round_trip_16() {
+        round_trip::<ChaskeyLTS>();
+    }
+
+    /// PDUs verify with any field sizes, in order, and with gaps
+    /// smaller than the window.  With MACs of at least 32 bits,
+    /// tampering with any bit that is not padding makes them fail.
+    fn round_trip<P: Permutation>() {
+        fn prop<P: Permutation>(payloads: Vec<Vec<u8>>, fv_bits: u8, mac_bits: u8,
+                                skips: Vec<bool>) -> bool {
+            let fv_bits = fv_bits as u32 % 65;
+            let mac_bits = 1 + mac_bits as u32 % 128;
+            let window = if fv_bits >= 2 { 3 } else { 1 };
+            let config = Config::new(7, fv_bits, mac_bits, window).unwrap();
+            let mut sender: Sender<P> = Sender::new(KEY, config, 100);
+            let mut receiver: Receiver<P> = Receiver::new(KEY, config, 99);
+            for (i, payload) in payloads.iter().enumerate() {
+                // Lose a PDU now and then, if the window allows.
+                if window > 1 && skips.get(i).cloned().unwrap_or(false) {
+                    sender.secure(b"lost").unwrap();
+                }
+                let mut pdu = sender.secure(payload).unwrap();
+                if mac_bits >= 32 {
+                    let meaningful = 8 * payload.len() + (fv_bits + mac_bits) as usize;
+                    let bit = i % meaningful;
+                    pdu[bit / 8] ^= 0x80 >> (bit % 8);
+                    if receiver.verify(&pdu).is_ok() {
+                        return false;
+                    }
+                    pdu[bit / 8] ^= 0x80 >> (bit % 8);
+                }
+                if receiver.verify(&pdu) != Ok(&payload[..]) {
+                    return false;
+                }
+            }
+            true
+        }
+        quickcheck(prop::<P> as fn(Vec<Vec<u8>>, u8, u8, Vec<bool>) -> bool);
+    }
+
+    #[test]
+    fn freshness() {
+        let config = Config::new(1, 4, 32, 3).unwrap();
+        let mut sender: Sender<Chaskey> = Sender::new(KEY, config, 1);
+        let mut receiver: Receiver<Chaskey> = Receiver::new(KEY, config, 0);
+        let pdus: Vec<Vec<u8>> = (0..40).map(|_| sender.secure(b"x").unwrap()).collect();
+
+        // In order, across the wraparound of the truncated counter.
+        for pdu in &pdus[..20] {
+            assert!(receiver.verify(pdu).is_ok());
+        }
+        assert_eq!(receiver.latest(), 20);
+        // A replay looks a whole cycle of the truncated counter ahead;
+        // an old PDU may look fresh, but then its MAC doesn't match.
+        assert_eq!(receiver.verify(&pdus[19]), Err(SecOcError::Freshness));
+        assert_eq!(receiver.verify(&pdus[4]), Err(SecOcError::Authentication));
+        // Two lost PDUs are within the window; three are not.
+        assert!(receiver.verify(&pdus[22]).is_ok());
+        assert_eq!(receiver.verify(&pdus[26]), Err(SecOcError::Freshness));
+        assert_eq!(receiver.latest(), 23);
+        assert!(receiver.verify(&pdus[23]).is_ok());
+    }
+
+    #[test]
+    fn full_freshness() {
+        let config = Config::new(1, 64, 64, 10).unwrap();
+        let mut sender: Sender<Chaskey> = Sender::new(KEY, config, u64::MAX - 1);
+        let mut receiver: Receiver<Chaskey> = Receiver::new(KEY, config, 0);
+        let pdu = sender.secure(b"").unwrap();
+        assert_eq!(receiver.verify(&pdu), Err(SecOcError::Freshness));
+        let mut receiver: Receiver<Chaskey> = Receiver::new(KEY, config, u64::MAX - 5);
+        assert!(receiver.verify(&pdu).is_ok());
+        assert_eq!(receiver.verify(&pdu), Err(SecOcError::Freshness));
+        assert_eq!(sender.secure(b"").err(), Some(SecOcError::FreshnessExhausted));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(Config::new(0, 65, 32, 1), Err(SecOcError::InvalidConfig));
+        assert_eq!(Config::new(0, 8, 0, 1), Err(SecOcError::InvalidConfig));
+        assert_eq!(Config::new(0, 8, 129, 1), Err(SecOcError::InvalidConfig));
+        assert_eq!(Config::new(0, 8, 32, 0), Err(SecOcError::InvalidConfig));
+        assert_eq!(Config::new(0, 2, 32, 5), Err(SecOcError::InvalidConfig));
+        assert!(Config::new(0, 2, 32, 4).is_ok());
+
+        let config = Config::new(0, 8, 24, 1).unwrap();
+        let mut receiver: Receiver<Chaskey> = Receiver::new(KEY, config, 0);
+        assert_eq!(receiver.verify(&[1, 2, 3]), Err(SecOcError::TooShort));
+    }
+}
@@ -0,0 +1,214 @@
+//! `Serialize` and `Deserialize` implementations, for the `serde`
+//! feature.
+//!
+//! * `Tag` and `TruncatedTag` are hex strings in human-readable
+//!   formats (JSON, TOML, ...) and byte strings in binary ones (CBOR,
+//!   bincode, ...).  Either encoding is accepted when deserializing,
+//!   as is a sequence of bytes, but the length is always checked:
+//!   exactly 16 bytes for a `Tag`, 1 to 16 for a `TruncatedTag`.
+//! * `Variant` is its name (`"chaskey-8"`, etc.).
+//! * `KeyFile` is its text format, so deserializing one checks its
+//!   version and check value like parsing a file does.
+
+use core::Variant;
+use hex::{FromHex, ToHex};
+use keyfile::KeyFile;
+use serde::de::{self, Deserialize, Deserializer, SeqAccess, Unexpected, Visitor};
+use serde::ser::{Serialize, Serializer};
+use std::fmt;
+use util::read_u32x4;
+use {Tag, TruncatedTag};
+
+
+fn serialize_bytes<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+    if serializer.is_human_readable() {
+        serializer.serialize_str(&bytes.to_hex())
+    } else {
+        serializer.serialize_bytes(bytes)
+    }
+}
+
+fn deserialize_bytes<'de, D: Deserializer<'de>>(deserializer: D, visitor: TagVisitor)
+                                               -> Result<([u8; 16], usize), D::Error> {
+    if deserializer.is_human_readable() {
+        deserializer.deserialize_str(visitor)
+    } else {
+        deserializer.deserialize_bytes(visitor)
+    }
+}
+
+/// Reads tag bytes from any of the encodings, and checks that there
+/// are between `min` and 16 of them.
+struct TagVisitor {
+    min: usize
+}
+
+impl<'de> Visitor<'de> for TagVisitor {
+    type Value = ([u8; 16], usize);
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.min == 16 {
+            f.write_str("a 16-byte Chaskey tag")
+        } else {
+            write!(f, "a Chaskey tag of {} to 16 bytes", self.min)
+        }
+    }
+
+    fn visit_str<E: de::Error>(self, s: &str) -> Result<Self::Value, E> {
+        let bytes = s.from_hex().map_err(|_| E::invalid_value(Unexpected::Str(s), &self))?;
+        self.visit_bytes(&bytes)
+    }
+
+    fn visit_bytes<E: de::Error>(self, bytes: &[u8]) -> Result<Self::Value, E> {
+        if bytes.len() < self.min || bytes.len() > 16 {
+            return Err(E::invalid_length(bytes.len(), &self));
+        }
+        let mut result = [0u8; 16];
+        result[..bytes.len()].copy_from_slice(bytes);
+        Ok((result, bytes.len()))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut result = [0u8; 16];
+        let mut len = 0;
+        while let Some(byte) = seq.next_element()? {
+            if len == 16 {
+                return Err(de::Error::invalid_length(len + 1, &self));
+            }
+            result[len] = byte;
+            len += 1;
+        }
+        if len < self.min {
+            return Err(de::Error::invalid_length(len, &self));
+        }
+        Ok((result, len))
+    }
+}
+
+
+impl Serialize for Tag {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_bytes(&self.raw_bytes(), serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Tag {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Tag, D::Error> {
+        let (bytes, _) = deserialize_bytes(deserializer, TagVisitor { min: 16 })?;
+        Ok(Tag(read_u32x4(&bytes)))
+    }
+}
+
+impl Serialize for TruncatedTag {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_bytes(self.as_bytes(), serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for TruncatedTag {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<TruncatedTag, D::Error> {
+        let (bytes, len) = deserialize_bytes(deserializer, TagVisitor { min: 1 })?;
+        Ok(TruncatedTag::new(&bytes[..len]).expect("length checked by TagVisitor"))
+    }
+}
+
+
+impl Serialize for Variant {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
+    }
+}
+
+impl<'de> Deserialize<'de> for Variant {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Variant, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        name.parse().map_err(|_| {
+            de::Error::invalid_value(Unexpected::Str(&name), &"a Chaskey variant name")
+        })
+    }
+}
+
+impl Serialize for KeyFile {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for KeyFile {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<KeyFile, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        text.parse().map_err(de::Error::custom)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use core::Variant;
+    use keyfile::KeyFile;
+    use serde_test::{Compact, Configure, Readable, Token};
+    use serde_test::{assert_de_tokens, assert_de_tokens_error, assert_tokens};
+    use {Tag, TruncatedTag};
+
+    /// The 8-round tag of the 3-byte message `00 01 02`, under the key
+    /// of the official test vectors.
+    const TAG: [u32; 4] = [0x1BDBB264, 0xA07680D8, 0x8E5B2AB8, 0x20660413];
+    const TAG_HEX: &str = "64b2db1bd88076a0b82a5b8e13046620";
+    const TAG_BYTES: &[u8] = &[0x64, 0xb2, 0xdb, 0x1b, 0xd8, 0x80, 0x76, 0xa0,
+                               0xb8, 0x2a, 0x5b, 0x8e, 0x13, 0x04, 0x66, 0x20];
+
+    #[test]
+    fn tags() {
+        let tag = Tag::new(TAG);
+        assert_tokens(&tag.readable(), &[Token::Str(TAG_HEX)]);
+        assert_tokens(&tag.compact(), &[Token::Bytes(TAG_BYTES)]);
+        assert_de_tokens(&tag.readable(), &[Token::Str("64B2DB1BD88076A0B82A5B8E13046620")]);
+
+        let mut seq = vec![Token::Seq { len: Some(16) }];
+        seq.extend(TAG_BYTES.iter().map(|&b| Token::U8(b)));
+        seq.push(Token::SeqEnd);
+        assert_de_tokens(&tag.compact(), &seq);
+    }
+
+    #[test]
+    fn tag_lengths() {
+        assert_de_tokens_error::<Readable<Tag>>(
+            &[Token::Str("64b2db1b")],
+            "invalid length 4, expected a 16-byte Chaskey tag");
+        assert_de_tokens_error::<Compact<Tag>>(
+            &[Token::Bytes(&[0; 17])],
+            "invalid length 17, expected a 16-byte Chaskey tag");
+        assert_de_tokens_error::<Readable<Tag>>(
+            &[Token::Str("not hex")],
+            "invalid value: string \"not hex\", expected a 16-byte Chaskey tag");
+        assert_de_tokens_error::<Compact<TruncatedTag>>(
+            &[Token::Bytes(&[])],
+            "invalid length 0, expected a Chaskey tag of 1 to 16 bytes");
+    }
+
+    #[test]
+    fn truncated_tags() {
+        let short = Tag::new(TAG).truncate(4);
+        assert_tokens(&short.readable(), &[Token::Str("64b2db1b")]);
+        assert_tokens(&short.compact(), &[Token::Bytes(&TAG_BYTES[..4])]);
+    }
+
+    #[test]
+    fn variants() {
+        assert_tokens(&Variant::ChaskeyLTS, &[Token::Str("chaskey-lts")]);
+        assert_de_tokens(&Variant::Chaskey12, &[Token::Str("12")]);
+        assert_de_tokens_error::<Variant>(
+            &[Token::Str("chaskey-9")],
+            "invalid value: string \"chaskey-9\", expected a Chaskey variant name");
+    }
+
+    #[test]
+    fn key_files() {
+        let file = KeyFile::new(Variant::Chaskey12, [1, 2, 3, 4], Some("k1")).unwrap();
+        let text = file.to_string();
+        assert_de_tokens(&file, &[Token::String(Box::leak(text.into_boxed_str()))]);
+        assert_de_tokens_error::<KeyFile>(
+            &[Token::Str("variant: chaskey-8\n")],
+            "not a Chaskey key file (no `chaskey-key` header)");
+    }
+}
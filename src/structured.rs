@@ -0,0 +1,402 @@
+//! MACs over structured input: sequences of typed fields, encoded so
+//! that different inputs never run together.
+//!
+//! Writing fields to a `Digester` one after another is ambiguous:
+//! `"ab"` then `"c"` gives the same tag as `"a"` then `"bc"`.  A
+//! `StructuredDigester` instead writes each field with its type and
+//! length in front:
+//!
+//! ```text
+//! field = type (1 byte) || length (8 bytes, little-endian) || content
+//! ```
+//!
+//! | Type | Written by      | Length              | Content                  |
+//! |------|-----------------|---------------------|--------------------------|
+//! | 1    | `bytes`         | byte count          | the bytes                |
+//! | 2    | `str`           | byte count          | the UTF-8 bytes          |
+//! | 3    | `uint`          | 8                   | the `u64`, little-endian |
+//! | 4    | `int`           | 8                   | the `i64`, little-endian |
+//! | 5    | `bool`          | 1                   | 0 or 1                   |
+//! | 6    | `seq`           | number of items     | none (the items follow)  |
+//!
+//! Integers of every width are widened to 64 bits, so services that
+//! store a field in different integer types still agree on tags.
+//!
+//! Types implement `MacInput` to say which fields they consist of.
+//! It is implemented for integers, `bool`, strings, byte slices,
+//! `Option`s and tuples; for your own types, write the fields in a
+//! fixed order:
+//!
+//! ```
+//! # extern crate chaskey;
+//! use chaskey::Chaskey12;
+//! use chaskey::structured::{self, MacInput, StructuredDigester};
+//! use chaskey::core::Permutation;
+//!
+//! struct Reading {
+//!     sensor: String,
+//!     time: u64,
+//!     celsius: i32
+//! }
+//!
+//! impl MacInput for Reading {
+//!     fn mac_fields<P: Permutation>(&self, mac: &mut StructuredDigester<P>) {
+//!         mac.str(&self.sensor);
+//!         mac.uint(self.time);
+//!         mac.int(self.celsius as i64);
+//!     }
+//! }
+//!
+//! # fn main() {
+//! let key = [1, 2, 3, 4];
+//! let reading = Reading { sensor: "boiler".to_string(), time: 1_700_000_000, celsius: 65 };
+//! let tag = structured::mac::<Chaskey12, _>(key, &reading);
+//!
+//! // The same fields, written by hand.
+//! let mut mac: StructuredDigester<Chaskey12> = StructuredDigester::new(key);
+//! mac.write(&("boiler", 1_700_000_000u64, 65i32));
+//! assert!(mac.finish() == tag);
+//! # }
+//! ```
+
+use core::Permutation;
+use {Digester, Tag};
+
+const BYTES: u8 = 1;
+const STR: u8 = 2;
+const UINT: u8 = 3;
+const INT: u8 = 4;
+const BOOL: u8 = 5;
+const SEQ: u8 = 6;
+
+
+/// A Chaskey digester for sequences of typed fields.
+pub struct StructuredDigester<P> {
+    digester: Digester<P>
+}
+
+impl<P: Permutation> StructuredDigester<P> {
+    /// Initialize a structured digester with the given key.
+    pub fn new(key: [u32; 4]) -> StructuredDigester<P> {
+        StructuredDigester { digester: Digester::new(key) }
+    }
+
+    /// A structured digester on top of
+    /// `Digester::with_personalization`.
+    pub fn with_personalization(key: [u32; 4], context: &[u8]) -> StructuredDigester<P> {
+        StructuredDigester { digester: Digester::with_personalization(key, context) }
+    }
+
+    /// Reset the digester, ready for a new sequence of fields.
+    pub fn reset(&mut self) {
+        self.digester.reset();
+    }
+
+    /// Write a byte string field.
+    pub fn bytes(&mut self, bytes: &[u8]) {
+        self.field(BYTES, bytes.len() as u64, bytes);
+    }
+
+    /// Write a string field.
+    pub fn str(&mut self, s: &str) {
+        self.field(STR, s.len() as u64, s.as_bytes());
+    }
+
+    /// Write an unsigned integer field.
+    pub fn uint(&mut self, n: u64) {
+        self.field(UINT, 8, &n.to_le_bytes());
+    }
+
+    /// Write a signed integer field.
+    pub fn int(&mut self, n: i64) {
+        self.field(INT, 8, &n.to_le_bytes());
+    }
+
+    /// Write a boolean field.
+    pub fn bool(&mut self, b: bool) {
+        self.field(BOOL, 1, &[b as u8]);
+    }
+
+    /// Write a sequence: its length, then each item's fields.
+    pub fn seq<T: MacInput>(&mut self, items: &[T]) {
+        self.field(SEQ, items.len() as u64, &[]);
+        for item in items {
+            item.mac_fields(self);
+        }
+    }
+
+    /// Write the fields of a value.
+    pub fn write<T: MacInput + ?Sized>(&mut self, value: &T) {
+        value.mac_fields(self);
+    }
+
+    /// The tag of the fields written so far.
+    pub fn finish(&self) -> Tag {
+        self.digester.finish()
+    }
+
+    fn field(&mut self, kind: u8, len: u64, content: &[u8]) {
+        self.digester.write(&[kind]);
+        self.digester.write(&len.to_le_bytes());
+        self.digester.write(content);
+    }
+}
+
+/// The tag of a value's fields.
+pub fn mac<P: Permutation, T: MacInput + ?Sized>(key: [u32; 4], value: &T) -> Tag {
+    let mut mac: StructuredDigester<P> = StructuredDigester::new(key);
+    mac.write(value);
+    mac.finish()
+}
+
+
+/// Types that can be authenticated as a fixed sequence of fields.
+///
+/// Two values of a type should write the same fields, in the same
+/// order, exactly when they are equal.  Writing a variable number of
+/// fields is fine as long as the fields written so far determine how
+/// many follow, as with `Option` and `StructuredDigester::seq`.
+pub trait MacInput {
+    /// Write the value's fields to `mac`.
+    fn mac_fields<P: Permutation>(&self, mac: &mut StructuredDigester<P>);
+}
+
+impl<T: MacInput + ?Sized> MacInput for &T {
+    fn mac_fields<P: Permutation>(&self, mac: &mut StructuredDigester<P>) {
+        (**self).mac_fields(mac);
+    }
+}
+
+impl MacInput for [u8] {
+    fn mac_fields<P: Permutation>(&self, mac: &mut StructuredDigester<P>) {
+        mac.bytes(self);
+    }
+}
+
+impl MacInput for Vec<u8> {
+    fn mac_fields<P: Permutation>(&self, mac: &mut StructuredDigester<P>) {
+        mac.bytes(self);
+    }
+}
+
+impl MacInput for str {
+    fn mac_fields<P: Permutation>(&self, mac: &mut StructuredDigester<P>) {
+        mac.str(self);
+    }
+}
+
+impl MacInput for String {
+    fn mac_fields<P: Permutation>(&self, mac: &mut StructuredDigester<P>) {
+        mac.str(self);
+    }
+}
+
+impl MacInput for bool {
+    fn mac_fields<P: Permutation>(&self, mac: &mut StructuredDigester<P>) {
+        mac.bool(*self);
+    }
+}
+
+impl MacInput for u8 {
+    fn mac_fields<P: Permutation>(&self, mac: &mut StructuredDigester<P>) {
+        mac.uint(*self as u64);
+    }
+}
+
+impl MacInput for u16 {
+    fn mac_fields<P: Permutation>(&self, mac: &mut StructuredDigester<P>) {
+        mac.uint(*self as u64);
+    }
+}
+
+impl MacInput for u32 {
+    fn mac_fields<P: Permutation>(&self, mac: &mut StructuredDigester<P>) {
+        mac.uint(*self as u64);
+    }
+}
+
+impl MacInput for u64 {
+    fn mac_fields<P: Permutation>(&self, mac: &mut StructuredDigester<P>) {
+        mac.uint(*self);
+    }
+}
+
+impl MacInput for usize {
+    fn mac_fields<P: Permutation>(&self, mac: &mut StructuredDigester<P>) {
+        mac.uint(*self as u64);
+    }
+}
+
+impl MacInput for i8 {
+    fn mac_fields<P: Permutation>(&self, mac: &mut StructuredDigester<P>) {
+        mac.int(*self as i64);
+    }
+}
+
+impl MacInput for i16 {
+    fn mac_fields<P: Permutation>(&self, mac: &mut StructuredDigester<P>) {
+        mac.int(*self as i64);
+    }
+}
+
+impl MacInput for i32 {
+    fn mac_fields<P: Permutation>(&self, mac: &mut StructuredDigester<P>) {
+        mac.int(*self as i64);
+    }
+}
+
+impl MacInput for i64 {
+    fn mac_fields<P: Permutation>(&self, mac: &mut StructuredDigester<P>) {
+        mac.int(*self);
+    }
+}
+
+impl MacInput for isize {
+    fn mac_fields<P: Permutation>(&self, mac: &mut StructuredDigester<P>) {
+        mac.int(*self as i64);
+    }
+}
+
+/// A `bool` field for whether there is a value, then the value's
+/// fields if there is one.
+impl<T: MacInput> MacInput for Option<T> {
+    fn mac_fields<P: Permutation>(&self, mac: &mut StructuredDigester<P>) {
+        mac.bool(self.is_some());
+        if let Some(ref value) = *self {
+            value.mac_fields(mac);
+        }
+    }
+}
+
+/// The fields of each element in turn.
+impl<A: MacInput, B: MacInput> MacInput for (A, B) {
+    fn mac_fields<P: Permutation>(&self, mac: &mut StructuredDigester<P>) {
+        self.0.mac_fields(mac);
+        self.1.mac_fields(mac);
+    }
+}
+
+impl<A: MacInput, B: MacInput, C: MacInput> MacInput for (A, B, C) {
+    fn mac_fields<P: Permutation>(&self, mac: &mut StructuredDigester<P>) {
+        self.0.mac_fields(mac);
+        self.1.mac_fields(mac);
+        self.2.mac_fields(mac);
+    }
+}
+
+impl<A: MacInput, B: MacInput, C: MacInput, D: MacInput> MacInput for (A, B, C, D) {
+    fn mac_fields<P: Permutation>(&self, mac: &mut StructuredDigester<P>) {
+        self.0.mac_fields(mac);
+        self.1.mac_fields(mac);
+        self.2.mac_fields(mac);
+        self.3.mac_fields(mac);
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use core::*;
+    use quickcheck::quickcheck;
+    use super::{mac, MacInput, StructuredDigester};
+    use {Digester, Tag};
+
+    const KEY: [u32; 4] = [0x833D3433, 0x009F389F, 0x2398E64F, 0x417ACF39];
+
+    fn tag<T: MacInput + ?Sized>(value: &T) -> Tag {
+        mac::<Chaskey, T>(KEY, value)
+    }
+
+    #[test]
+    fn encoding() {
+        let mut expected: Digester<Chaskey> = Digester::new(KEY);
+        expected.write(&[2, 2, 0, 0, 0, 0, 0, 0, 0]);
+        expected.write(b"ab");
+        expected.write(&[3, 8, 0, 0, 0, 0, 0, 0, 0, 0x34, 0x12, 0, 0, 0, 0, 0, 0]);
+        expected.write(&[4, 8, 0, 0, 0, 0, 0, 0, 0, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]);
+        expected.write(&[5, 1, 0, 0, 0, 0, 0, 0, 0, 1]);
+        expected.write(&[6, 2, 0, 0, 0, 0, 0, 0, 0]);
+        expected.write(&[1, 1, 0, 0, 0, 0, 0, 0, 0, 0xaa]);
+        expected.write(&[1, 0, 0, 0, 0, 0, 0, 0, 0]);
+        expected.write(&[5, 1, 0, 0, 0, 0, 0, 0, 0, 0]);
+
+        let mut mac: StructuredDigester<Chaskey> = StructuredDigester::new(KEY);
+        mac.write(&("ab", 0x1234u16, -1i8, true));
+        mac.seq(&[vec![0xaa], vec![]]);
+        mac.write(&None::<u8>);
+        assert!(mac.finish() == expected.finish());
+
+        mac.reset();
+        assert!(mac.finish() == Digester::<Chaskey>::new(KEY).finish());
+
+        let mut mac: StructuredDigester<Chaskey> =
+            StructuredDigester::with_personalization(KEY, b"context");
+        mac.bool(false);
+        let mut expected: Digester<Chaskey> = Digester::with_personalization(KEY, b"context");
+        expected.write(&[5, 1, 0, 0, 0, 0, 0, 0, 0, 0]);
+        assert!(mac.finish() == expected.finish());
+    }
+
+    #[test]
+    fn unambiguous() {
+        assert!(tag(&("ab", "c")) != tag(&("a", "bc")));
+        assert!(tag(&(&b"ab"[..], &b"c"[..])) != tag(&(&b"a"[..], &b"bc"[..])));
+        assert!(tag("ab") != tag(&b"ab"[..]));
+        assert!(tag(&1u64) != tag(&1i64));
+        assert!(tag(&1u8) != tag(&true));
+        assert!(tag(&None::<u8>) != tag(&Some(0u8)));
+        // An `Option` is a `bool` and then the value, if any.
+        assert!(tag(&Some(0u8)) == tag(&(true, 0u8)));
+        assert!(tag("") != tag(&("", "")));
+        // Integer widths don't matter.
+        assert!(tag(&7u8) == tag(&7u64));
+        assert!(tag(&-7i16) == tag(&-7i64));
+    }
+
+    #[test]
+    fn user_types() {
+        struct Point { x: i32, y: i32, label: Option<String> }
+
+        impl MacInput for Point {
+            fn mac_fields<P: Permutation>(&self, mac: &mut StructuredDigester<P>) {
+                mac.int(self.x as i64);
+                mac.int(self.y as i64);
+                mac.write(&self.label);
+            }
+        }
+
+        let p = Point { x: 1, y: -2, label: Some("origin".to_string()) };
+        assert!(tag(&p) == tag(&(1i32, -2i64, Some("origin"))));
+        let mut mac: StructuredDigester<Chaskey> = StructuredDigester::new(KEY);
+        mac.seq(&[&p, &p]);
+        assert!(mac.finish() != tag(&p));
+    }
+
+    #[test]
+    fn pairs_8() {
+        pairs::<Chaskey>();
+    }
+
+    #[test]
+    fn pairs_12() {
+        pairs::<Chaskey12>();
+    }
+
+    #[test]
+    fn pairs_16() {
+        pairs::<ChaskeyLTS>();
+    }
+
+    /// Pairs of byte strings get the same tag only if they are equal,
+    /// even when their concatenations are.
+    fn pairs<P: Permutation>() {
+        fn prop<P: Permutation>(a: (Vec<u8>, Vec<u8>), split: usize) -> bool {
+            let mut joined = a.0.clone();
+            joined.extend_from_slice(&a.1);
+            let split = split % (joined.len() + 1);
+            let b = (joined[..split].to_vec(), joined[split..].to_vec());
+            (a == b) == (mac::<P, _>(KEY, &a) == mac::<P, _>(KEY, &b))
+        }
+        quickcheck(prop::<P> as fn((Vec<u8>, Vec<u8>), usize) -> bool);
+    }
+}
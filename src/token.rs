@@ -0,0 +1,342 @@
+//! Short-lived signed tokens, such as session cookies.
+//!
+//! A token is URL-safe base64, without padding, of:
+//!
+//! ```text
+//! token  = version || key id || expiry || payload || tag
+//! tag    = first 12 bytes of Chaskey(K', version || key id || expiry || payload)
+//! K'     = the key personalized with "chaskey-token-v1"
+//! ```
+//!
+//! The version is 1 byte (currently 1), the key id 1 byte, and the
+//! expiry 8 bytes, big-endian, in seconds since the Unix epoch.  A
+//! token is valid until, but not at, its expiry time.  Tokens are
+//! signed, not encrypted, so anyone can read their payloads.
+//!
+//! The tags are made with `Digester::with_personalization`, so a key
+//! can also be used for other MACs without tokens and other tags ever
+//! being mistaken for each other.
+//!
+//! ```
+//! # extern crate chaskey;
+//! use chaskey::ChaskeyLTS;
+//! use chaskey::clock::ManualClock;
+//! use chaskey::token::{Issuer, TokenError, Verifier};
+//!
+//! # fn main() {
+//! let clock = ManualClock::new(1_700_000_000);
+//! let key = [1, 2, 3, 4];
+//! let mut issuer: Issuer<ChaskeyLTS, _> = Issuer::with_clock(5, key, &clock);
+//! let mut verifier: Verifier<ChaskeyLTS, _> = Verifier::with_clock(&clock);
+//! verifier.add_key(5, key);
+//!
+//! let token = issuer.issue(b"user=alice", 900);
+//! assert_eq!(verifier.verify(&token).unwrap().payload(), b"user=alice");
+//!
+//! clock.advance(900);
+//! assert_eq!(verifier.verify(&token), Err(TokenError::Expired(1_700_000_900)));
+//! # }
+//! ```
+
+use base64::{decode_url, encode_url};
+use clock::{Clock, SystemClock};
+use core::Permutation;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use {Digester, TruncatedTag};
+
+/// The format version this module issues and accepts.
+pub const VERSION: u8 = 1;
+
+/// The length of a token's tag, in bytes.
+pub const TAG_LEN: usize = 12;
+
+/// The length of the fields before the payload, in bytes.
+const HEADER_LEN: usize = 10;
+
+/// The personalization context of the tags.
+const CONTEXT: &[u8] = b"chaskey-token-v1";
+
+
+/// Issues tokens signed with one key.
+pub struct Issuer<P, C = SystemClock> {
+    key_id: u8,
+    digester: Digester<P>,
+    clock: C
+}
+
+impl<P: Permutation> Issuer<P, SystemClock> {
+    /// An issuer that uses the system clock.
+    pub fn new(key_id: u8, key: [u32; 4]) -> Issuer<P, SystemClock> {
+        Issuer::with_clock(key_id, key, SystemClock)
+    }
+}
+
+impl<P: Permutation, C: Clock> Issuer<P, C> {
+    /// An issuer that uses `clock` to compute expiry times.
+    pub fn with_clock(key_id: u8, key: [u32; 4], clock: C) -> Issuer<P, C> {
+        Issuer { key_id, digester: Digester::with_personalization(key, CONTEXT), clock }
+    }
+
+    /// Issue a token for `payload` that is valid for `lifetime`
+    /// seconds from now.
+    pub fn issue(&mut self, payload: &[u8], lifetime: u64) -> String {
+        let expires = self.clock.now().saturating_add(lifetime);
+        let mut token = Vec::with_capacity(HEADER_LEN + payload.len() + TAG_LEN);
+        token.push(VERSION);
+        token.push(self.key_id);
+        token.extend_from_slice(&expires.to_be_bytes());
+        token.extend_from_slice(payload);
+        let tag = tag(&mut self.digester, &token);
+        token.extend_from_slice(tag.as_bytes());
+        encode_url(&token)
+    }
+}
+
+
+/// Verifies tokens signed with any of a set of keys.
+pub struct Verifier<P, C = SystemClock> {
+    keys: BTreeMap<u8, Digester<P>>,
+    clock: C
+}
+
+impl<P: Permutation> Verifier<P, SystemClock> {
+    /// A verifier with no keys that uses the system clock.
+    pub fn new() -> Verifier<P, SystemClock> {
+        Verifier::with_clock(SystemClock)
+    }
+}
+
+impl<P: Permutation> Default for Verifier<P, SystemClock> {
+    fn default() -> Verifier<P, SystemClock> {
+        Verifier::new()
+    }
+}
+
+impl<P: Permutation, C: Clock> Verifier<P, C> {
+    /// A verifier with no keys that uses `clock` to check expiry
+    /// times.
+    pub fn with_clock(clock: C) -> Verifier<P, C> {
+        Verifier { keys: BTreeMap::new(), clock }
+    }
+
+    /// Accept tokens with key id `key_id`, signed with `key`.  This
+    /// replaces any key that had the same id.
+    pub fn add_key(&mut self, key_id: u8, key: [u32; 4]) {
+        self.keys.insert(key_id, Digester::with_personalization(key, CONTEXT));
+    }
+
+    /// Stop accepting tokens with key id `key_id`.  Returns whether
+    /// there was such a key.
+    pub fn remove_key(&mut self, key_id: u8) -> bool {
+        self.keys.remove(&key_id).is_some()
+    }
+
+    /// Verify a token, and return its contents.  Tokens whose tag
+    /// doesn't match are reported as `Forged` whether or not they
+    /// have expired; `Expired` means the token was authentic.
+    pub fn verify(&mut self, token: &str) -> Result<Token, TokenError> {
+        let bytes = decode_url(token).ok_or(TokenError::Malformed)?;
+        if bytes.len() < HEADER_LEN + TAG_LEN {
+            return Err(TokenError::Malformed);
+        }
+        if bytes[0] != VERSION {
+            return Err(TokenError::UnsupportedVersion(bytes[0]));
+        }
+        let key_id = bytes[1];
+        let digester = self.keys.get_mut(&key_id).ok_or(TokenError::UnknownKey(key_id))?;
+
+        let (signed, received) = bytes.split_at(bytes.len() - TAG_LEN);
+        if TruncatedTag::new(received) != Some(tag(digester, signed)) {
+            return Err(TokenError::Forged);
+        }
+        let mut expiry = [0u8; 8];
+        expiry.copy_from_slice(&signed[2..HEADER_LEN]);
+        let expires = u64::from_be_bytes(expiry);
+        if self.clock.now() >= expires {
+            return Err(TokenError::Expired(expires));
+        }
+        Ok(Token { key_id, expires, payload: signed[HEADER_LEN..].to_vec() })
+    }
+}
+
+fn tag<P: Permutation>(digester: &mut Digester<P>, signed: &[u8]) -> TruncatedTag {
+    digester.reset();
+    digester.write(signed);
+    digester.finish().truncate(TAG_LEN)
+}
+
+
+/// The contents of a verified token.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Token {
+    key_id: u8,
+    expires: u64,
+    payload: Vec<u8>
+}
+
+impl Token {
+    /// The id of the key that signed the token.
+    pub fn key_id(&self) -> u8 {
+        self.key_id
+    }
+
+    /// The time the token expires, in seconds since the Unix epoch.
+    pub fn expires(&self) -> u64 {
+        self.expires
+    }
+
+    /// The token's payload, as issued.
+    pub fn payload(&self) -> &[u8] {
+        &self.payload
+    }
+}
+
+
+/// Why a token was rejected.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TokenError {
+    /// The token isn't valid base64url, or is too short.
+    Malformed,
+    /// The token has a format version other than `VERSION`.
+    UnsupportedVersion(u8),
+    /// The verifier has no key with this id.
+    UnknownKey(u8),
+    /// The tag doesn't match.
+    Forged,
+    /// The token is authentic, but expired at the given time.
+    Expired(u64)
+}
+
+impl fmt::Display for TokenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TokenError::Malformed => write!(f, "malformed token"),
+            TokenError::UnsupportedVersion(v) => write!(f, "unsupported token version {}", v),
+            TokenError::UnknownKey(id) => write!(f, "unknown token key id {}", id),
+            TokenError::Forged => write!(f, "token verification failed"),
+            TokenError::Expired(t) => write!(f, "token expired at {}", t)
+        }
+    }
+}
+
+impl Error for TokenError { }
+
+
+#[cfg(test)]
+mod tests {
+    use base64::{decode_url, encode_url};
+    use clock::ManualClock;
+    use core::*;
+    use quickcheck::quickcheck;
+    use super::{Issuer, TokenError, Verifier, CONTEXT, TAG_LEN};
+    use Digester;
+
+    const KEY: [u32; 4] = [0x833D3433, 0x009F389F, 0x2398E64F, 0x417ACF39];
+    const NOW: u64 = 1_700_000_000;
+
+    #[test]
+    fn layout() {
+        let clock = ManualClock::new(NOW);
+        let mut issuer: Issuer<Chaskey, _> = Issuer::with_clock(7, KEY, &clock);
+        let token = decode_url(&issuer.issue(b"hi", 60)).unwrap();
+        let mut signed = vec![1, 7];
+        signed.extend_from_slice(&(NOW + 60).to_be_bytes());
+        signed.extend_from_slice(b"hi");
+        assert_eq!(token[..12], signed[..]);
+
+        let mut d: Digester<Chaskey> = Digester::with_personalization(KEY, CONTEXT);
+        d.write(&signed);
+        assert_eq!(token[12..], d.finish().raw_bytes()[..TAG_LEN]);
+    }
+
+    #[test]
+    fn round_trip_8() {
+        round_trip::<Chaskey>();
+    }
+
+    #[test]
+    fn round_trip_12() {
+        round_trip::<Chaskey12>();
+    }
+
+    #[test]
+    fn round_trip_16() {
+        round_trip::<ChaskeyLTS>();
+    }
+
+    fn round_trip<P: Permutation>() {
+        fn prop<P: Permutation>(payload: Vec<u8>, lifetime: u32) -> bool {
+            let clock = ManualClock::new(NOW);
+            let lifetime = 1 + lifetime as u64;
+            let mut issuer: Issuer<P, _> = Issuer::with_clock(3, KEY, &clock);
+            let mut verifier: Verifier<P, _> = Verifier::with_clock(&clock);
+            verifier.add_key(3, KEY);
+            let token = issuer.issue(&payload, lifetime);
+            let ok = match verifier.verify(&token) {
+                Ok(t) => t.payload() == &payload[..] && t.key_id() == 3
+                    && t.expires() == NOW + lifetime,
+                Err(_) => false
+            };
+            clock.advance(lifetime - 1);
+            let still_ok = verifier.verify(&token).is_ok();
+            clock.advance(1);
+            ok && still_ok && verifier.verify(&token) == Err(TokenError::Expired(NOW + lifetime))
+        }
+        quickcheck(prop::<P> as fn(Vec<u8>, u32) -> bool);
+    }
+
+    #[test]
+    fn errors() {
+        let clock = ManualClock::new(NOW);
+        let mut issuer: Issuer<Chaskey, _> = Issuer::with_clock(1, KEY, &clock);
+        let mut verifier: Verifier<Chaskey, _> = Verifier::with_clock(&clock);
+        verifier.add_key(1, KEY);
+        let token = issuer.issue(b"payload", 60);
+        let bytes = decode_url(&token).unwrap();
+
+        assert_eq!(verifier.verify("not a token!"), Err(TokenError::Malformed));
+        assert_eq!(verifier.verify(&format!("{}=", token)), Err(TokenError::Malformed));
+        assert_eq!(verifier.verify(&encode_url(&bytes[..21])), Err(TokenError::Malformed));
+
+        let mut other = bytes.clone();
+        other[0] = 2;
+        assert_eq!(verifier.verify(&encode_url(&other)), Err(TokenError::UnsupportedVersion(2)));
+        other[0] = 1;
+        other[1] = 9;
+        assert_eq!(verifier.verify(&encode_url(&other)), Err(TokenError::UnknownKey(9)));
+
+        // Changing any byte after the key id, such as the expiry or
+        // the tag, is a forgery, even once the token has expired.
+        for i in 2..bytes.len() {
+            let mut forged = bytes.clone();
+            forged[i] ^= 0x01;
+            assert_eq!(verifier.verify(&encode_url(&forged)), Err(TokenError::Forged));
+        }
+        clock.advance(3600);
+        let mut forged = bytes.clone();
+        forged[9] ^= 0xff;
+        assert_eq!(verifier.verify(&encode_url(&forged)), Err(TokenError::Forged));
+        assert_eq!(verifier.verify(&token), Err(TokenError::Expired(NOW + 60)));
+
+        // A different key under the same id.
+        let mut verifier: Verifier<Chaskey, _> = Verifier::with_clock(&clock);
+        verifier.add_key(1, [1, 2, 3, 4]);
+        assert_eq!(verifier.verify(&token), Err(TokenError::Forged));
+        assert!(verifier.remove_key(1));
+        assert_eq!(verifier.verify(&token), Err(TokenError::UnknownKey(1)));
+    }
+
+    #[test]
+    fn key_rotation() {
+        let clock = ManualClock::new(NOW);
+        let mut old: Issuer<Chaskey12, _> = Issuer::with_clock(1, KEY, &clock);
+        let mut new: Issuer<Chaskey12, _> = Issuer::with_clock(2, [5, 6, 7, 8], &clock);
+        let mut verifier: Verifier<Chaskey12, _> = Verifier::with_clock(&clock);
+        verifier.add_key(1, KEY);
+        verifier.add_key(2, [5, 6, 7, 8]);
+        assert_eq!(verifier.verify(&old.issue(b"a", 10)).unwrap().key_id(), 1);
+        assert_eq!(verifier.verify(&new.issue(b"b", 10)).unwrap().key_id(), 2);
+    }
+}
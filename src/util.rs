@@ -21,3 +21,23 @@ pub fn xor_u8x16(state: &mut [u32; 4], block: &[u8; 16]) {
 }
 
 
+
+/// Read a `[u32; 4]` value from 16 little-endian bytes.
+#[inline]
+pub fn read_u32x4(bytes: &[u8]) -> [u32; 4] {
+    [LittleEndian::read_u32(&bytes[0..4]),
+     LittleEndian::read_u32(&bytes[4..8]),
+     LittleEndian::read_u32(&bytes[8..12]),
+     LittleEndian::read_u32(&bytes[12..16])]
+}
+
+/// Write a `[u32; 4]` value as 16 little-endian bytes.
+#[inline]
+pub fn write_u32x4(words: &[u32; 4]) -> [u8; 16] {
+    let mut result = [0u8; 16];
+    LittleEndian::write_u32(&mut result[0..4], words[0]);
+    LittleEndian::write_u32(&mut result[4..8], words[1]);
+    LittleEndian::write_u32(&mut result[8..12], words[2]);
+    LittleEndian::write_u32(&mut result[12..16], words[3]);
+    result
+}
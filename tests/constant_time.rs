@@ -0,0 +1,181 @@
+//! A [dudect](https://eprint.iacr.org/2016/1123)-style timing test of
+//! `Tag` and `TruncatedTag` comparison.
+//!
+//! The test times comparisons of random tags against three classes of
+//! other tags: equal ones, ones that differ in the first word, and
+//! ones that differ only in the last word.  The classes are randomly
+//! interleaved, and Welch's t-test is applied to each pair of them.
+//! A |t| above 4.5 means the timings of the classes are very likely
+//! to differ: the comparison leaks where (or whether) the tags
+//! differ.
+//!
+//! Timing tests are slow and sensitive to the machine, so they are
+//! opt-in, and only meaningful in release builds:
+//!
+//! ```text
+//! cargo test --release --test constant_time -- --ignored --nocapture
+//! ```
+//!
+//! The `CHASKEY_DUDECT_SAMPLES` environment variable sets the number
+//! of measurements (default 1,000,000).
+
+extern crate chaskey;
+extern crate rand;
+
+use chaskey::{Tag, TruncatedTag};
+use rand::Rng;
+use std::env;
+use std::hint::black_box;
+
+
+/// The |t| above which we report leakage, as in dudect.
+const THRESHOLD: f64 = 4.5;
+
+/// Comparisons per measurement, to get well above the timer's
+/// resolution.
+const BATCH: usize = 16;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Class { Equal, EarlyDiff, LateDiff }
+
+const CLASSES: [Class; 3] = [Class::Equal, Class::EarlyDiff, Class::LateDiff];
+
+#[cfg(target_arch = "x86_64")]
+fn cycles() -> u64 {
+    unsafe { std::arch::x86_64::_rdtsc() }
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn cycles() -> u64 {
+    use std::time::Instant;
+    thread_local!(static START: Instant = Instant::now());
+    START.with(|start| start.elapsed().as_nanos() as u64)
+}
+
+/// Running mean and variance (Welford's algorithm).
+#[derive(Clone, Copy, Default)]
+struct Stats { n: f64, mean: f64, m2: f64 }
+
+impl Stats {
+    fn push(&mut self, x: f64) {
+        self.n += 1.0;
+        let delta = x - self.mean;
+        self.mean += delta / self.n;
+        self.m2 += delta * (x - self.mean);
+    }
+
+    fn variance(&self) -> f64 {
+        self.m2 / (self.n - 1.0)
+    }
+}
+
+/// Welch's t statistic for the difference of two means.
+fn welch_t(a: &Stats, b: &Stats) -> f64 {
+    (a.mean - b.mean) / (a.variance() / a.n + b.variance() / b.n).sqrt()
+}
+
+fn samples() -> usize {
+    env::var("CHASKEY_DUDECT_SAMPLES").ok()
+        .map(|s| s.parse().expect("CHASKEY_DUDECT_SAMPLES must be a number"))
+        .unwrap_or(1_000_000)
+}
+
+/// Time `eq` on the three classes of inputs, each pair of tags first
+/// converted with `prepare`, and return the largest |t| over each
+/// pair of classes and each cropping percentile.
+fn measure<T, P, F>(name: &str, prepare: P, eq: F) -> f64
+    where P: Fn(Tag) -> T, F: Fn(&T, &T) -> bool
+{
+    let mut rng = rand::thread_rng();
+    let n = samples();
+
+    // Prepare all inputs up front, so that only the comparisons are
+    // timed.
+    let mut inputs = Vec::with_capacity(n);
+    for _ in 0..n {
+        let words: [u32; 4] = [rng.gen(), rng.gen(), rng.gen(), rng.gen()];
+        let class = CLASSES[rng.gen_range(0, CLASSES.len())];
+        let mut other = words;
+        match class {
+            Class::Equal => (),
+            Class::EarlyDiff => other[0] ^= 1 << rng.gen_range(0, 32),
+            Class::LateDiff => other[3] ^= 1 << rng.gen_range(0, 32)
+        }
+        inputs.push((class, prepare(Tag::new(words)), prepare(Tag::new(other))));
+    }
+
+    let mut times = Vec::with_capacity(n);
+    for &(class, ref a, ref b) in inputs.iter() {
+        let start = cycles();
+        for _ in 0..BATCH {
+            black_box(eq(black_box(a), black_box(b)));
+        }
+        times.push((class, cycles() - start));
+    }
+
+    // Like dudect, also test the measurements under a few
+    // percentiles, since the upper tail is mostly interrupts and
+    // other noise.
+    let mut sorted: Vec<u64> = times.iter().map(|&(_, t)| t).collect();
+    sorted.sort();
+    let mut worst: f64 = 0.0;
+    for &pct in [100, 99, 90, 50].iter() {
+        let cutoff = sorted[(sorted.len() - 1) * pct / 100];
+        let mut stats = [Stats::default(); 3];
+        for &(class, t) in times.iter().filter(|&&(_, t)| t <= cutoff) {
+            stats[class as usize].push(t as f64);
+        }
+        for &(i, j) in [(0, 1), (0, 2), (1, 2)].iter() {
+            let t = welch_t(&stats[i], &stats[j]);
+            println!("{}: {:?} vs {:?}, under {}th percentile: t = {:.2}",
+                     name, CLASSES[i], CLASSES[j], pct, t);
+            if t.abs() > worst {
+                worst = t.abs();
+            }
+        }
+    }
+    println!("{}: max |t| = {:.2} ({})", name, worst,
+             if worst > THRESHOLD { "leakage detected" } else { "no leakage detected" });
+    worst
+}
+
+#[test]
+#[ignore]
+fn tag_eq_constant_time() {
+    let t = measure("Tag::eq", |tag| tag, |a, b| a == b);
+    assert!(t <= THRESHOLD, "Tag::eq timing depends on its inputs (|t| = {:.2})", t);
+}
+
+#[test]
+#[ignore]
+fn truncated_tag_eq_constant_time() {
+    // Eight bytes: the tag's first and last words, so that both kinds
+    // of difference survive the truncation.
+    fn truncate(tag: Tag) -> TruncatedTag {
+        let bytes = tag.raw_bytes();
+        let mut short = [0u8; 8];
+        short[..4].copy_from_slice(&bytes[..4]);
+        short[4..].copy_from_slice(&bytes[12..]);
+        TruncatedTag::new(&short).unwrap()
+    }
+    let t = measure("TruncatedTag::eq", truncate, |a, b| a == b);
+    assert!(t <= THRESHOLD, "TruncatedTag::eq timing depends on its inputs (|t| = {:.2})", t);
+}
+
+/// Check that the harness can detect leakage at all, with a
+/// comparison that stops at the first differing byte.
+#[test]
+#[ignore]
+fn harness_detects_early_exit() {
+    fn leaky(a: &Tag, b: &Tag) -> bool {
+        let (a, b) = (a.raw_bytes(), b.raw_bytes());
+        for i in 0..16 {
+            if black_box(a[i]) != black_box(b[i]) {
+                return false;
+            }
+        }
+        true
+    }
+    let t = measure("early-exit comparison", |tag| tag, leaky);
+    assert!(t > THRESHOLD, "the harness didn't detect an early-exit comparison (|t| = {:.2})", t);
+}
@@ -0,0 +1,49 @@
+//! Run the known-answer tests in `tests/kat`.
+
+extern crate chaskey;
+
+use chaskey::Variant;
+use chaskey::kat::{self, Vector};
+use std::fs::File;
+use std::io::Read;
+use std::path::PathBuf;
+
+
+fn load(variant: Variant) -> Vec<Vector> {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests").join("kat").join(format!("{}.kat", variant));
+    let mut text = String::new();
+    File::open(&path)
+        .and_then(|mut f| f.read_to_string(&mut text))
+        .unwrap_or_else(|e| panic!("can't read {}: {}", path.display(), e));
+    kat::parse(&text).unwrap_or_else(|e| panic!("{}: {}", path.display(), e))
+}
+
+fn run(variant: Variant) {
+    let vectors = load(variant);
+    let macs = vectors.iter().filter(|v| matches!(**v, Vector::Mac { .. }));
+    let ciphers = vectors.iter().filter(|v| matches!(**v, Vector::Cipher { .. }));
+    assert!(macs.count() >= 64, "too few MAC vectors for {}", variant);
+    assert!(ciphers.count() > 0, "no cipher vectors for {}", variant);
+
+    let failures: Vec<String> = vectors.iter()
+        .filter(|v| v.variant() != variant || !v.check())
+        .map(|v| format!("{:?}", v))
+        .collect();
+    assert!(failures.is_empty(), "{} failing vector(s):\n{}", failures.len(), failures.join("\n"));
+}
+
+#[test]
+fn kat_chaskey_8() {
+    run(Variant::Chaskey);
+}
+
+#[test]
+fn kat_chaskey_12() {
+    run(Variant::Chaskey12);
+}
+
+#[test]
+fn kat_chaskey_lts() {
+    run(Variant::ChaskeyLTS);
+}
@@ -0,0 +1,112 @@
+/*
+ * Chaskey MAC reference implementation, after the public domain code
+ * by Nicky Mouha (http://mouha.be/chaskey/).
+ *
+ * Differences from the original:
+ *
+ *  - The number of rounds is a parameter instead of a compile-time
+ *    choice, so one build covers all three variants.
+ *  - Message words are loaded byte by byte as little-endian, instead
+ *    of by casting the message pointer, so the code is portable and
+ *    has no alignment requirements.
+ *
+ * This file is only used for testing; it is deliberately written
+ * independently of the Rust code in `src/`.
+ */
+
+#include <string.h>
+
+#include "chaskey.h"
+
+#define ROTL(x,b) (uint32_t)( ((x) >> (32 - (b))) | ( (x) << (b)) )
+
+#define ROUND \
+  do { \
+    v[0] += v[1]; v[1]=ROTL(v[1], 5); v[1] ^= v[0]; v[0]=ROTL(v[0],16); \
+    v[2] += v[3]; v[3]=ROTL(v[3], 8); v[3] ^= v[2]; \
+    v[0] += v[3]; v[3]=ROTL(v[3],13); v[3] ^= v[0]; \
+    v[2] += v[1]; v[1]=ROTL(v[1], 7); v[1] ^= v[2]; v[2]=ROTL(v[2],16); \
+  } while(0)
+
+#define TIMESTWO(out,in) \
+  do { \
+    out[0] = (in[0] << 1) ^ C[in[3] >> 31]; \
+    out[1] = (in[1] << 1) | (in[0] >> 31); \
+    out[2] = (in[2] << 1) | (in[1] >> 31); \
+    out[3] = (in[3] << 1) | (in[2] >> 31); \
+  } while(0)
+
+static const volatile uint32_t C[2] = { 0x00, 0x87 };
+
+static uint32_t load32(const uint8_t *p) {
+  return (uint32_t) p[0] | ((uint32_t) p[1] << 8)
+    | ((uint32_t) p[2] << 16) | ((uint32_t) p[3] << 24);
+}
+
+static void store32(uint8_t *p, uint32_t x) {
+  p[0] = (uint8_t) x;
+  p[1] = (uint8_t) (x >> 8);
+  p[2] = (uint8_t) (x >> 16);
+  p[3] = (uint8_t) (x >> 24);
+}
+
+static void permute(uint32_t v[4], int rounds) {
+  int r;
+  for (r = 0; r < rounds; r++) {
+    ROUND;
+  }
+}
+
+void chaskey_ref_subkeys(uint32_t k1[4], uint32_t k2[4], const uint32_t k[4]) {
+  TIMESTWO(k1,k);
+  TIMESTWO(k2,k1);
+}
+
+void chaskey_ref_mac(uint8_t *tag, uint32_t taglen,
+                     const uint8_t *m, uint32_t mlen,
+                     const uint32_t k[4], const uint32_t k1[4], const uint32_t k2[4],
+                     int rounds) {
+  const uint8_t *M = m;
+  /* pointer to last message block */
+  const uint8_t *end = mlen == 0 ? m : m + (((mlen-1)>>4)<<4);
+  const uint32_t *l;
+  uint8_t lb[16];
+  uint8_t out[16];
+  uint32_t v[4];
+  int i;
+
+  v[0] = k[0];
+  v[1] = k[1];
+  v[2] = k[2];
+  v[3] = k[3];
+
+  for ( ; M != end; M += 16 ) {
+    for (i = 0; i < 4; i++) {
+      v[i] ^= load32(M + 4*i);
+    }
+    permute(v, rounds);
+  }
+
+  if ((mlen != 0) && ((mlen & 0xF) == 0)) {
+    l = k1;
+    memcpy(lb, M, 16);
+  } else {
+    l = k2;
+    i = (int) (m + mlen - M);
+    memcpy(lb, M, i);
+    lb[i++] = 0x01; /* padding bit */
+    for ( ; i != 16; i++) {
+      lb[i] = 0;
+    }
+  }
+
+  for (i = 0; i < 4; i++) {
+    v[i] ^= load32(lb + 4*i) ^ l[i];
+  }
+  permute(v, rounds);
+  for (i = 0; i < 4; i++) {
+    v[i] ^= l[i];
+    store32(out + 4*i, v[i]);
+  }
+  memcpy(tag, out, taglen);
+}
@@ -0,0 +1,20 @@
+/*
+ * Chaskey MAC reference implementation, after the public domain code
+ * by Nicky Mouha (http://mouha.be/chaskey/), extended with a round
+ * count so that it covers Chaskey (8 rounds), Chaskey-12 and
+ * Chaskey-LTS (16 rounds).
+ */
+
+#ifndef CHASKEY_REFERENCE_H
+#define CHASKEY_REFERENCE_H
+
+#include <stdint.h>
+
+void chaskey_ref_subkeys(uint32_t k1[4], uint32_t k2[4], const uint32_t k[4]);
+
+void chaskey_ref_mac(uint8_t *tag, uint32_t taglen,
+                     const uint8_t *m, uint32_t mlen,
+                     const uint32_t k[4], const uint32_t k1[4], const uint32_t k2[4],
+                     int rounds);
+
+#endif
@@ -0,0 +1,41 @@
+/*
+ * Print the 64 incremental test vectors for a Chaskey variant: the
+ * tags under the published test key of the messages 00, 00 01, ...,
+ * 00 01 ... 3e (starting with the empty message), as Rust `Tag`
+ * literals.
+ *
+ *     cc -o vectors tests/reference/vectors.c tests/reference/chaskey.c
+ *     ./vectors 16
+ */
+
+#include <stdio.h>
+#include <stdlib.h>
+
+#include "chaskey.h"
+
+static const uint32_t key[4] = { 0x833D3433, 0x009F389F, 0x2398E64F, 0x417ACF39 };
+
+int main(int argc, char **argv) {
+  uint32_t k1[4], k2[4];
+  uint8_t m[64];
+  uint8_t tag[16];
+  int rounds, i, j;
+
+  if (argc != 2 || (rounds = atoi(argv[1])) <= 0) {
+    fprintf(stderr, "usage: %s ROUNDS\n", argv[0]);
+    return 2;
+  }
+
+  chaskey_ref_subkeys(k1, k2, key);
+  for (i = 0; i < 64; i++) {
+    m[i] = (uint8_t) i;
+    chaskey_ref_mac(tag, 16, m, i, key, k1, k2, rounds);
+    printf("        Tag([");
+    for (j = 0; j < 4; j++) {
+      printf("0x%02X%02X%02X%02X%s", tag[4*j+3], tag[4*j+2], tag[4*j+1], tag[4*j],
+             j == 3 ? "" : ", ");
+    }
+    printf("]),\n");
+  }
+  return 0;
+}